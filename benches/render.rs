@@ -0,0 +1,52 @@
+//! `cargo bench`: [`Renderer::render`] in every mode, on test pattern
+//! frames with the box moving between them so motion and the cell cache
+//! behave as they do live.
+
+use criterion::{Criterion, criterion_group, criterion_main};
+use webcii::{
+    render::{
+        Renderer,
+        color::Colorizer,
+        edges::EDGE_THRESHOLD,
+        settings::{Mode, RenderSettings},
+    },
+    source::{ColorSpace, Frame, pattern::TestPattern},
+};
+
+const FRAME_SIZE: (u32, u32) = (640, 360);
+const GRID: (usize, usize) = (160, 48);
+
+fn render(c: &mut Criterion) {
+    let pattern = TestPattern::new(FRAME_SIZE.0, FRAME_SIZE.1, 30);
+    let frames: Vec<Frame> = (0..2)
+        .map(|n| Frame::new(pattern.frame(n * 15), "test".into(), ColorSpace::Srgb))
+        .collect();
+
+    let mut group = c.benchmark_group("render");
+    let mut mode = Mode::Ascii;
+    loop {
+        let settings = RenderSettings {
+            mode,
+            colorizer: Colorizer::TrueColor,
+            ..RenderSettings::default()
+        };
+        let mut renderer = Renderer::new(settings.colorizer, EDGE_THRESHOLD);
+        let mut n = 0;
+        group.bench_function(format!("{:?}", mode), |b| {
+            b.iter(|| {
+                n += 1;
+                renderer
+                    .render(&frames[n % frames.len()], GRID.0, GRID.1, &settings)
+                    .unwrap()
+            })
+        });
+        mode = mode.next();
+        if mode == Mode::Ascii {
+            break;
+        }
+    }
+    group.finish();
+}
+
+criterion_group!(benches, render);
+criterion_main!(benches);
@@ -0,0 +1,202 @@
+use std::{
+    env,
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use clap::{ArgMatches, parser::ValueSource};
+use serde::{Deserialize, Serialize};
+use webcii::render::{charset, edges::EDGE_THRESHOLD};
+
+use crate::cli::{Args, DEFAULT_TARGET_FRAME_TIME_MS};
+
+/// Startup settings, resolved from built-in defaults, the config file, a
+/// restored session and command-line flags, in that order of precedence.
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    pub camera: u32,
+    /// Requested capture resolution; used only when both are set.
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+    /// Requested capture frame rate.
+    pub fps: Option<u32>,
+    pub sobel_threshold: f32,
+    pub target_frame_time: u64,
+    /// Fixed canvas as `[columns, rows]` instead of the terminal's size.
+    pub size: Option<(u32, u32)>,
+    /// Restrict output to 7-bit ASCII glyphs.
+    pub ascii_only: bool,
+    /// No color or title escapes; implies `ascii_only`.
+    pub plain: bool,
+    /// Glyph ramp to start with, like `--charset`.
+    pub charset: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            camera: 0,
+            width: None,
+            height: None,
+            fps: None,
+            sobel_threshold: EDGE_THRESHOLD,
+            target_frame_time: DEFAULT_TARGET_FRAME_TIME_MS,
+            size: None,
+            ascii_only: false,
+            plain: false,
+            charset: None,
+        }
+    }
+}
+
+impl Config {
+    /// `$XDG_CONFIG_HOME/webcii/config.toml`, falling back to
+    /// `~/.config/webcii/config.toml`.
+    pub fn default_path() -> Option<PathBuf> {
+        let base = env::var_os("XDG_CONFIG_HOME")
+            .filter(|dir| !dir.is_empty())
+            .map(PathBuf::from)
+            .or_else(|| env::var_os("HOME").map(|home| Path::new(&home).join(".config")))?;
+        Some(base.join("webcii").join("config.toml"))
+    }
+
+    /// Loads `path` if given (it must exist), otherwise the default location
+    /// if there is a file there, otherwise built-in defaults.
+    pub fn load(path: Option<&Path>) -> Result<Self, Box<dyn Error>> {
+        let (path, required) = match path {
+            Some(path) => (path.to_path_buf(), true),
+            None => match Self::default_path() {
+                Some(path) => (path, false),
+                None => return Ok(Self::default()),
+            },
+        };
+
+        match fs::read_to_string(&path) {
+            Ok(text) => {
+                let config: Self =
+                    toml::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+                if let Some(ramp) = &config.charset {
+                    charset::parse_ramp(ramp)
+                        .map_err(|e| format!("{}: charset: {}", path.display(), e))?;
+                }
+                Ok(config)
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound && !required => Ok(Self::default()),
+            Err(e) => Err(format!("{}: {}", path.display(), e).into()),
+        }
+    }
+
+    /// Grid to render onto: the fixed canvas if there is one, otherwise the
+    /// terminal's `(columns, rows)`.
+    pub fn canvas(&self, terminal: (usize, usize)) -> (usize, usize) {
+        self.size
+            .map_or(terminal, |(cols, rows)| (cols as usize, rows as usize))
+    }
+
+    /// Requested capture resolution, if both dimensions are set.
+    pub fn resolution(&self) -> Option<(u32, u32)> {
+        self.width.zip(self.height)
+    }
+
+    /// Overrides values with the flags that were actually given on the
+    /// command line; clap defaults don't count.
+    pub fn apply_args(&mut self, args: &Args, matches: &ArgMatches) {
+        let given = |id: &str| matches.value_source(id) == Some(ValueSource::CommandLine);
+
+        if given("camera") {
+            self.camera = args.camera;
+        }
+        if given("width") {
+            self.width = args.width;
+            self.height = args.height;
+        }
+        if given("fps") {
+            self.fps = args.fps;
+        }
+        if given("sobel_threshold") {
+            self.sobel_threshold = args.sobel_threshold;
+        }
+        if given("target_frame_time") {
+            self.target_frame_time = args.target_frame_time;
+        }
+        if given("size") {
+            self.size = args.size;
+        }
+        if given("ascii_only") {
+            self.ascii_only = true;
+        }
+        if given("plain") {
+            self.plain = true;
+        }
+        if given("charset") {
+            self.charset = args.charset.clone();
+        }
+        if given("charset_preset") {
+            self.charset = args.charset_preset.map(String::from);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::{CommandFactory, FromArgMatches};
+
+    #[test]
+    fn file_values_fill_in_and_flags_win() {
+        let mut config: Config = toml::from_str("camera = 3\nsobel_threshold = 45.0\n").unwrap();
+        assert_eq!(config.target_frame_time, DEFAULT_TARGET_FRAME_TIME_MS);
+
+        let matches = Args::command()
+            .try_get_matches_from(["webcii", "--camera", "1", "--fps", "15"])
+            .unwrap();
+        let args = Args::from_arg_matches(&matches).unwrap();
+        config.apply_args(&args, &matches);
+
+        assert_eq!(config.camera, 1);
+        assert_eq!(config.fps, Some(15));
+        assert_eq!(config.width, None);
+        assert_eq!(config.sobel_threshold, 45.0);
+    }
+
+    #[test]
+    fn charset_flags_replace_the_file_ramp() {
+        let mut config: Config = toml::from_str("charset = \" .:#\"\n").unwrap();
+        let matches = Args::command()
+            .try_get_matches_from(["webcii", "--camera", "1"])
+            .unwrap();
+        config.apply_args(&Args::from_arg_matches(&matches).unwrap(), &matches);
+        assert_eq!(config.charset.as_deref(), Some(" .:#"));
+
+        let matches = Args::command()
+            .try_get_matches_from(["webcii", "--charset", "@%#*+=-:. "])
+            .unwrap();
+        config.apply_args(&Args::from_arg_matches(&matches).unwrap(), &matches);
+        assert_eq!(config.charset.as_deref(), Some("@%#*+=-:. "));
+    }
+
+    #[test]
+    fn bad_charsets_are_rejected_on_load() {
+        let path = env::temp_dir().join(format!("webcii-config-{}.toml", std::process::id()));
+        fs::write(&path, "charset = \"x\"\n").unwrap();
+        let result = Config::load(Some(&path));
+        let _ = fs::remove_file(&path);
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn resolution_flags_go_together() {
+        assert!(
+            Args::command()
+                .try_get_matches_from(["webcii", "--width", "640"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(toml::from_str::<Config>("camrea = 1").is_err());
+    }
+}
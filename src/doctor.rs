@@ -0,0 +1,366 @@
+//! `webcii doctor`: checks the environment for the usual reasons webcii
+//! can't see a camera or draw properly, and says how to fix each one.
+
+use std::{env, fmt, io::IsTerminal};
+
+use webcii::{source::devices, term};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Status {
+    Pass,
+    /// Works, but probably not as well as it could.
+    Warn,
+    Fail,
+    /// Doesn't apply here, e.g. a Linux check on macOS.
+    Skip,
+}
+
+impl fmt::Display for Status {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Status::Pass => "pass",
+            Status::Warn => "warn",
+            Status::Fail => "FAIL",
+            Status::Skip => "skip",
+        })
+    }
+}
+
+pub struct Check {
+    pub name: &'static str,
+    pub status: Status,
+    pub detail: String,
+    pub fix: Option<String>,
+}
+
+impl Check {
+    fn new(name: &'static str, status: Status, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            status,
+            detail: detail.into(),
+            fix: None,
+        }
+    }
+
+    fn fix(mut self, fix: impl Into<String>) -> Self {
+        self.fix = Some(fix.into());
+        self
+    }
+}
+
+/// Runs every check for this platform.
+pub fn run() -> Vec<Check> {
+    vec![
+        camera_access(),
+        #[cfg(target_os = "linux")]
+        linux::video_devices(),
+        #[cfg(target_os = "linux")]
+        linux::video_group(),
+        #[cfg(target_os = "linux")]
+        linux::wayland_portal(),
+        terminal(),
+        colors(
+            env::var("NO_COLOR").ok(),
+            env::var("COLORTERM").ok(),
+            env::var("TERM").ok(),
+        ),
+        locale(
+            env::var("LC_ALL").ok(),
+            env::var("LC_CTYPE").ok(),
+            env::var("LANG").ok(),
+        ),
+    ]
+}
+
+/// One line per check, with the fix indented under the ones that need one.
+pub fn report(checks: &[Check]) -> String {
+    let mut report = String::new();
+    for check in checks {
+        report += &format!("[{}] {}: {}\n", check.status, check.name, check.detail);
+        if let Some(fix) = &check.fix {
+            report += &format!("       fix: {}\n", fix);
+        }
+    }
+    report
+}
+
+fn camera_access() -> Check {
+    const NAME: &str = "camera access";
+    if let Err(e) = devices::request_camera_permission() {
+        let check = Check::new(NAME, Status::Fail, e.to_string());
+        return match e.hint() {
+            Some(hint) => check.fix(hint),
+            None => check,
+        };
+    }
+    match devices::list() {
+        Ok(cameras) if cameras.is_empty() => Check::new(NAME, Status::Warn, "no cameras found")
+            .fix("connect a camera, or try `webcii --source test`"),
+        Ok(cameras) => {
+            let names: Vec<_> = cameras
+                .iter()
+                .map(|(index, name)| format!("{} {}", index, name))
+                .collect();
+            Check::new(NAME, Status::Pass, names.join(", "))
+        }
+        Err(e) => {
+            let check = Check::new(NAME, Status::Fail, e.to_string());
+            match e.hint() {
+                Some(hint) => check.fix(hint),
+                None => check,
+            }
+        }
+    }
+}
+
+fn terminal() -> Check {
+    const NAME: &str = "terminal";
+    if !std::io::stdout().is_terminal() {
+        return Check::new(NAME, Status::Fail, "stdout is not a terminal")
+            .fix("run webcii in a terminal, or use `webcii image --once --plain` for text output");
+    }
+    match term::size() {
+        Ok((cols, rows)) if cols >= 40 && rows >= 12 => {
+            Check::new(NAME, Status::Pass, format!("{}x{} cells", cols, rows))
+        }
+        Ok((cols, rows)) => Check::new(NAME, Status::Warn, format!("only {}x{} cells", cols, rows))
+            .fix("enlarge the window or use a smaller font for a more detailed picture"),
+        Err(e) => Check::new(NAME, Status::Fail, e.to_string()),
+    }
+}
+
+/// The same variables `Colorizer::detect` looks at.
+fn colors(no_color: Option<String>, colorterm: Option<String>, term: Option<String>) -> Check {
+    const NAME: &str = "colors";
+    if no_color.is_some_and(|v| !v.is_empty()) {
+        return Check::new(
+            NAME,
+            Status::Warn,
+            "NO_COLOR is set, so webcii draws without color",
+        )
+        .fix("unset NO_COLOR, or press `c` to pick a colorizer");
+    }
+    if matches!(colorterm.as_deref(), Some("truecolor") | Some("24bit")) {
+        return Check::new(NAME, Status::Pass, "24-bit color");
+    }
+    match term.as_deref() {
+        Some("dumb") => Check::new(NAME, Status::Fail, "TERM=dumb can't move the cursor")
+            .fix("run webcii in a terminal emulator"),
+        Some(term) if term.ends_with("256color") => {
+            Check::new(NAME, Status::Warn, "the 256-color palette")
+                .fix("set COLORTERM=truecolor if the terminal (and tmux) pass 24-bit color")
+        }
+        Some("linux") | Some("ansi") | Some("cons25") => {
+            Check::new(NAME, Status::Warn, "only the 8 basic colors")
+                .fix("use a terminal emulator with 24-bit color and set COLORTERM=truecolor")
+        }
+        Some(term) if term.starts_with("vt") => {
+            Check::new(NAME, Status::Warn, "only the 8 basic colors")
+                .fix("use a terminal emulator with 24-bit color and set COLORTERM=truecolor")
+        }
+        _ => Check::new(
+            NAME,
+            Status::Warn,
+            "COLORTERM doesn't say truecolor, so 24-bit color is assumed",
+        )
+        .fix("set COLORTERM=truecolor if colors look right, or press `c` if they don't"),
+    }
+}
+
+/// The locale in effect is the first of `LC_ALL`, `LC_CTYPE` and `LANG`
+/// that is set.
+fn locale(lc_all: Option<String>, lc_ctype: Option<String>, lang: Option<String>) -> Check {
+    const NAME: &str = "locale";
+    let locale = [lc_all, lc_ctype, lang]
+        .into_iter()
+        .flatten()
+        .find(|value| !value.is_empty());
+    match locale {
+        Some(locale) if is_utf8(&locale) => Check::new(NAME, Status::Pass, locale),
+        Some(locale) => Check::new(NAME, Status::Warn, format!("{} is not UTF-8", locale))
+            .fix("set LANG to a UTF-8 locale such as en_US.UTF-8, or run with --ascii-only"),
+        None => Check::new(NAME, Status::Warn, "no locale set")
+            .fix("set LANG to a UTF-8 locale such as en_US.UTF-8, or run with --ascii-only"),
+    }
+}
+
+fn is_utf8(locale: &str) -> bool {
+    let codeset = locale.split(['.', '@']).nth(1).unwrap_or("");
+    codeset.eq_ignore_ascii_case("utf-8") || codeset.eq_ignore_ascii_case("utf8")
+}
+
+#[cfg(target_os = "linux")]
+mod linux {
+    use std::{
+        env, fs,
+        io::ErrorKind,
+        path::{Path, PathBuf},
+    };
+
+    use super::{Check, Status};
+
+    /// Whether the `/dev/video*` nodes can be opened.
+    pub fn video_devices() -> Check {
+        const NAME: &str = "video devices";
+        let mut nodes: Vec<PathBuf> = fs::read_dir("/dev")
+            .into_iter()
+            .flatten()
+            .flatten()
+            .map(|entry| entry.path())
+            .filter(|path| {
+                path.file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.starts_with("video"))
+            })
+            .collect();
+        nodes.sort();
+        if nodes.is_empty() {
+            return Check::new(NAME, Status::Warn, "no /dev/video* devices")
+                .fix("connect a camera; inside a container, pass it through with --device");
+        }
+
+        let denied: Vec<_> = nodes
+            .iter()
+            .filter(|node| {
+                fs::File::open(node).is_err_and(|e| e.kind() == ErrorKind::PermissionDenied)
+            })
+            .map(|node| node.display().to_string())
+            .collect();
+        if denied.is_empty() {
+            let names: Vec<_> = nodes.iter().map(|n| n.display().to_string()).collect();
+            Check::new(NAME, Status::Pass, names.join(", "))
+        } else {
+            Check::new(
+                NAME,
+                Status::Fail,
+                format!("permission denied on {}", denied.join(", ")),
+            )
+            .fix("add yourself to the video group (see below)")
+        }
+    }
+
+    pub fn video_group() -> Check {
+        const NAME: &str = "video group";
+        let group_file = fs::read_to_string("/etc/group").unwrap_or_default();
+        let Some(gid) = group_id(&group_file, "video") else {
+            return Check::new(NAME, Status::Skip, "no video group on this system");
+        };
+        let status = fs::read_to_string("/proc/self/status").unwrap_or_default();
+        if effective_uid(&status) == Some(0) {
+            Check::new(NAME, Status::Pass, "running as root")
+        } else if process_groups(&status).contains(&gid) {
+            Check::new(NAME, Status::Pass, "member of video")
+        } else {
+            Check::new(NAME, Status::Fail, "not a member of video")
+                .fix("sudo usermod -aG video $USER, then log out and back in")
+        }
+    }
+
+    /// Screen capture on Wayland goes through xdg-desktop-portal.
+    pub fn wayland_portal() -> Check {
+        const NAME: &str = "wayland portal";
+        if env::var_os("WAYLAND_DISPLAY").is_none() {
+            return Check::new(NAME, Status::Skip, "not a Wayland session");
+        }
+        let data_dirs = env::var("XDG_DATA_DIRS")
+            .ok()
+            .filter(|dirs| !dirs.is_empty())
+            .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+        let installed = data_dirs.split(':').any(|dir| {
+            Path::new(dir)
+                .join("dbus-1/services/org.freedesktop.portal.Desktop.service")
+                .exists()
+        });
+        if installed {
+            Check::new(NAME, Status::Pass, "xdg-desktop-portal is installed")
+        } else {
+            Check::new(NAME, Status::Fail, "xdg-desktop-portal is not installed").fix(
+                "install xdg-desktop-portal and the backend for your desktop \
+                 (e.g. xdg-desktop-portal-gnome) for `webcii screen`",
+            )
+        }
+    }
+
+    /// The id of `name` in an `/etc/group` file.
+    pub(super) fn group_id(group_file: &str, name: &str) -> Option<u32> {
+        group_file.lines().find_map(|line| {
+            let mut fields = line.split(':');
+            if fields.next()? != name {
+                return None;
+            }
+            fields.nth(1)?.parse().ok()
+        })
+    }
+
+    /// The effective user id from `/proc/self/status`.
+    pub(super) fn effective_uid(status: &str) -> Option<u32> {
+        let uids = status.lines().find_map(|line| line.strip_prefix("Uid:"))?;
+        uids.split_whitespace().nth(1)?.parse().ok()
+    }
+
+    /// Supplementary groups from `/proc/self/status`.
+    pub(super) fn process_groups(status: &str) -> Vec<u32> {
+        status
+            .lines()
+            .find_map(|line| line.strip_prefix("Groups:"))
+            .map(|groups| {
+                groups
+                    .split_whitespace()
+                    .filter_map(|gid| gid.parse().ok())
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn locale_precedence_and_codeset() {
+        let check = locale(None, Some("C".into()), Some("en_US.UTF-8".into()));
+        assert_eq!(check.status, Status::Warn);
+        assert_eq!(check.detail, "C is not UTF-8");
+
+        let check = locale(Some(String::new()), None, Some("de_DE.utf8@euro".into()));
+        assert_eq!(check.status, Status::Pass);
+        assert_eq!(locale(None, None, None).status, Status::Warn);
+    }
+
+    #[test]
+    fn colors_follow_the_detection_variables() {
+        let truecolor = colors(None, Some("truecolor".into()), Some("linux".into()));
+        assert_eq!(truecolor.status, Status::Pass);
+        let console = colors(None, None, Some("linux".into()));
+        assert_eq!(console.detail, "only the 8 basic colors");
+        let tmux = colors(None, None, Some("tmux-256color".into()));
+        assert_eq!(tmux.detail, "the 256-color palette");
+        assert_eq!(colors(Some("1".into()), None, None).status, Status::Warn);
+    }
+
+    #[test]
+    fn report_puts_fixes_under_their_check() {
+        let checks = [
+            Check::new("locale", Status::Pass, "en_US.UTF-8"),
+            Check::new("video group", Status::Fail, "not a member of video").fix("usermod"),
+        ];
+        assert_eq!(
+            report(&checks),
+            "[pass] locale: en_US.UTF-8\n\
+             [FAIL] video group: not a member of video\n       fix: usermod\n"
+        );
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn video_group_membership_is_read_from_proc() {
+        let groups = "root:x:0:\nvideo:x:44:alice\n";
+        assert_eq!(linux::group_id(groups, "video"), Some(44));
+        assert_eq!(linux::group_id(groups, "audio"), None);
+        let status = "Name:\twebcii\nUid:\t1000\t0\t1000\t1000\nGroups:\t4 24 44 \n";
+        assert_eq!(linux::process_groups(status), [4, 24, 44]);
+        assert_eq!(linux::effective_uid(status), Some(0));
+    }
+}
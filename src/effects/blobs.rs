@@ -0,0 +1,108 @@
+use std::{cmp::Reverse, collections::VecDeque};
+
+use super::overlay;
+
+/// Luma change between frames that marks a cell as moving.
+const MOTION_THRESHOLD: u8 = 24;
+/// Components smaller than this many cells are treated as noise.
+const MIN_BLOB_AREA: usize = 6;
+
+/// A connected group of moving cells, in terminal cell coordinates.
+pub struct Blob {
+    pub min_x: usize,
+    pub min_y: usize,
+    pub max_x: usize,
+    pub max_y: usize,
+    pub area: usize,
+    sum_x: usize,
+    sum_y: usize,
+}
+
+impl Blob {
+    pub fn centroid(&self) -> (usize, usize) {
+        (self.sum_x / self.area, self.sum_y / self.area)
+    }
+}
+
+/// Marks the cells of a `cols` x `rows` grid whose sampled luma changed by
+/// more than [`MOTION_THRESHOLD`] between two RGB frames of the same size.
+pub fn motion_mask(
+    current: &[u8],
+    previous: &[u8],
+    width: usize,
+    height: usize,
+    cols: usize,
+    rows: usize,
+) -> Vec<bool> {
+    if current.len() != previous.len() {
+        return vec![false; cols * rows];
+    }
+
+    (0..cols * rows)
+        .map(|i| {
+            let x = (i % cols) * width / cols;
+            let y = (i / cols) * height / rows;
+            let idx = (y * width + x) * 3;
+            let now = overlay::luma(current[idx], current[idx + 1], current[idx + 2]);
+            let before = overlay::luma(previous[idx], previous[idx + 1], previous[idx + 2]);
+            now.abs_diff(before) > MOTION_THRESHOLD
+        })
+        .collect()
+}
+
+/// 4-connected component labeling over `mask`, returning blobs of at least
+/// [`MIN_BLOB_AREA`] cells, largest first.
+pub fn find_blobs(mask: &[bool], cols: usize) -> Vec<Blob> {
+    let mut visited = vec![false; mask.len()];
+    let mut queue = VecDeque::new();
+    let mut blobs = Vec::new();
+
+    for start in 0..mask.len() {
+        if !mask[start] || visited[start] {
+            continue;
+        }
+
+        let mut blob = Blob {
+            min_x: usize::MAX,
+            min_y: usize::MAX,
+            max_x: 0,
+            max_y: 0,
+            area: 0,
+            sum_x: 0,
+            sum_y: 0,
+        };
+
+        visited[start] = true;
+        queue.push_back(start);
+        while let Some(i) = queue.pop_front() {
+            let (x, y) = (i % cols, i / cols);
+            blob.min_x = blob.min_x.min(x);
+            blob.min_y = blob.min_y.min(y);
+            blob.max_x = blob.max_x.max(x);
+            blob.max_y = blob.max_y.max(y);
+            blob.area += 1;
+            blob.sum_x += x;
+            blob.sum_y += y;
+
+            let neighbors = [
+                (x > 0).then(|| i - 1),
+                (x + 1 < cols).then(|| i + 1),
+                (y > 0).then(|| i - cols),
+                (i + cols < mask.len()).then(|| i + cols),
+            ];
+            for n in neighbors.into_iter().flatten() {
+                if mask[n] && !visited[n] {
+                    visited[n] = true;
+                    queue.push_back(n);
+                }
+            }
+        }
+
+        if blob.area >= MIN_BLOB_AREA {
+            blobs.push(blob);
+        }
+    }
+
+    blobs.sort_by_key(|blob| Reverse(blob.area));
+    blobs
+}
@@ -0,0 +1,96 @@
+use image::{Rgb, RgbImage};
+
+/// Per-cell count of frames with motion since the session started (or since
+/// the last resize, which invalidates cell positions).
+pub struct Heatmap {
+    counts: Vec<u32>,
+    grid: (usize, usize),
+    max: u32,
+}
+
+impl Default for Heatmap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Heatmap {
+    pub fn new() -> Self {
+        Self {
+            counts: Vec::new(),
+            grid: (0, 0),
+            max: 0,
+        }
+    }
+
+    /// Bytes held by the counts.
+    pub fn memory_usage(&self) -> usize {
+        self.counts.capacity() * size_of::<u32>()
+    }
+
+    /// Adds the motion `mask` of a `cols` x `rows` grid. Counts from a
+    /// grid of another shape are dropped, even one with as many cells.
+    pub fn accumulate(&mut self, mask: &[bool], cols: usize, rows: usize) {
+        if self.grid != (cols, rows) || self.counts.len() != mask.len() {
+            self.counts = vec![0; mask.len()];
+            self.grid = (cols, rows);
+            self.max = 0;
+        }
+        for (count, &moving) in self.counts.iter_mut().zip(mask) {
+            if moving {
+                *count += 1;
+                self.max = self.max.max(*count);
+            }
+        }
+    }
+
+    /// Heat color for cell `i`: black through blue and red to yellow for the
+    /// most active cell. `None` until the grid has been accumulated.
+    pub fn color(&self, i: usize) -> Option<[u8; 3]> {
+        let count = *self.counts.get(i)?;
+        let heat = (count as u64 * 767)
+            .checked_div(self.max as u64)
+            .unwrap_or(0) as u32;
+        let color = match heat {
+            0..=255 => [0, 0, heat as u8],
+            256..=511 => [(heat - 256) as u8, 0, (511 - heat) as u8],
+            _ => [255, (heat - 512).min(255) as u8, 0],
+        };
+        Some(color)
+    }
+
+    /// The heat colors as an image, one pixel per cell, for
+    /// `--heatmap-export`. `None` until the grid has been accumulated.
+    pub fn image(&self) -> Option<RgbImage> {
+        let (cols, rows) = self.grid;
+        if self.counts.is_empty() {
+            return None;
+        }
+        Some(RgbImage::from_fn(cols as u32, rows as u32, |x, y| {
+            Rgb(self.color(y as usize * cols + x as usize).unwrap_or([0; 3]))
+        }))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reshaped_grids_start_over() {
+        let mut heatmap = Heatmap::new();
+        let mut mask = vec![false; 800];
+        mask[0] = true;
+        heatmap.accumulate(&mask, 40, 20);
+        assert_eq!(heatmap.color(0), Some([255, 255, 0]));
+        // Same number of cells, different layout.
+        heatmap.accumulate(&vec![false; 800], 20, 40);
+        assert_eq!(heatmap.color(0), Some([0, 0, 0]));
+
+        mask[21] = true;
+        heatmap.accumulate(&mask, 20, 40);
+        let image = heatmap.image().unwrap();
+        assert_eq!(image.dimensions(), (20, 40));
+        assert_eq!(image.get_pixel(1, 1).0, [255, 255, 0]);
+    }
+}
@@ -0,0 +1,7 @@
+//! Analysis overlays drawn on top of the video: exposure aids, motion
+//! detection and tracking.
+
+pub mod blobs;
+pub mod heatmap;
+pub mod overlay;
+pub mod tracking;
@@ -0,0 +1,244 @@
+use serde::{Deserialize, Serialize};
+
+/// Luma at or above which a cell counts as clipped highlights.
+const ZEBRA_CLIP_LUMA: u8 = 245;
+/// Luma at or below which a cell counts as crushed shadows.
+const ZEBRA_CRUSH_LUMA: u8 = 10;
+
+const ZEBRA_CLIP_COLOR: [u8; 3] = [255, 32, 32];
+const ZEBRA_CRUSH_COLOR: [u8; 3] = [32, 96, 255];
+
+pub const BLOB_BOX_COLOR: [u8; 3] = [255, 200, 0];
+
+/// Sobel magnitude above which a cell is painted as in focus.
+pub const PEAKING_DEFAULT_THRESHOLD: f32 = 160.0;
+pub const PEAKING_THRESHOLD_STEP: f32 = 20.0;
+
+/// Highlight colors cycled by the peaking color hotkey.
+pub const PEAKING_COLORS: [[u8; 3]; 4] =
+    [[0, 255, 64], [255, 32, 32], [255, 255, 0], [0, 224, 255]];
+
+/// BT.601 luma, the same weighting the camera's exposure metering uses.
+pub fn luma(r: u8, g: u8, b: u8) -> u8 {
+    ((77 * r as u32 + 150 * g as u32 + 29 * b as u32) >> 8) as u8
+}
+
+/// Exposure zebras: clipped cells get `/` stripes, crushed cells `\` stripes
+/// running the other way, so the two stay distinguishable without color.
+pub fn zebra(tx: usize, ty: usize, luma: u8) -> Option<(char, [u8; 3])> {
+    if luma >= ZEBRA_CLIP_LUMA && (tx + ty) % 4 < 2 {
+        Some(('/', ZEBRA_CLIP_COLOR))
+    } else if luma <= ZEBRA_CRUSH_LUMA && (tx + 4 - ty % 4) % 4 < 2 {
+        Some(('\\', ZEBRA_CRUSH_COLOR))
+    } else {
+        None
+    }
+}
+
+/// What the cell colors represent.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum View {
+    /// Colors from the camera.
+    Normal,
+    /// Luma bands mapped onto a false-color exposure scale.
+    FalseColor,
+}
+
+impl View {
+    pub fn next(self) -> Self {
+        match self {
+            View::Normal => View::FalseColor,
+            View::FalseColor => View::Normal,
+        }
+    }
+}
+
+// Upper bound of each band as a percentage of full scale, paired with its
+// color. `None` means neutral grey at the pixel's own luma.
+const FALSE_COLOR_BANDS: [(u32, Option<[u8; 3]>); 10] = [
+    (2, Some([128, 0, 192])),    // crushed
+    (10, Some([0, 64, 255])),    // near black
+    (38, None),                  // shadows
+    (45, Some([0, 192, 64])),    // 18% grey
+    (52, None),                  // midtones
+    (58, Some([255, 128, 192])), // skin
+    (84, None),                  // highlights
+    (95, Some([224, 224, 160])), // bright
+    (99, Some([255, 224, 0])),   // near clipping
+    (100, Some([255, 0, 0])),    // clipped
+];
+
+/// Luma -> color lookup for [`View::FalseColor`], purple for crushed through
+/// red for clipped.
+pub fn false_color_lut() -> [[u8; 3]; 256] {
+    let mut lut = [[0; 3]; 256];
+    for (luma, entry) in lut.iter_mut().enumerate() {
+        let percent = luma as u32 * 100 / 255;
+        let band = FALSE_COLOR_BANDS
+            .iter()
+            .find(|(upper, _)| percent <= *upper)
+            .and_then(|(_, color)| *color);
+        *entry = band.unwrap_or([luma as u8; 3]);
+    }
+    lut
+}
+
+/// How much labels darken the video behind them by default, so their text
+/// stays readable over a busy picture.
+pub const DEFAULT_LABEL_DIM: f32 = 0.5;
+
+/// One cell of a [`Layer`], blended over the video cell under it.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct LayerCell {
+    /// Glyph drawn instead of the video's, or `None` to keep the video's
+    /// glyph and only tint it.
+    pub glyph: Option<char>,
+    pub color: [u8; 3],
+    /// How much of `color` covers the video's color, from 0 to 1.
+    pub alpha: f32,
+    /// How much the video under the cell is darkened first, from 0 to 1.
+    pub dim: f32,
+}
+
+impl LayerCell {
+    /// A glyph that hides the video cell's glyph and color completely.
+    pub fn opaque(glyph: char, color: [u8; 3]) -> Self {
+        Self {
+            glyph: Some(glyph),
+            color,
+            alpha: 1.0,
+            dim: 0.0,
+        }
+    }
+
+    /// Blends the cell over a video cell drawn as `glyph` in `color` on
+    /// `background`, with the cell's alpha scaled by `opacity`.
+    pub fn composite(
+        &self,
+        glyph: char,
+        color: [u8; 3],
+        background: Option<[u8; 3]>,
+        opacity: f32,
+    ) -> (char, [u8; 3], Option<[u8; 3]>) {
+        let darken = |c: [u8; 3]| blend(c, [0; 3], self.dim);
+        let color = blend(darken(color), self.color, self.alpha * opacity);
+        (self.glyph.unwrap_or(glyph), color, background.map(darken))
+    }
+}
+
+/// `under` with `over` laid on top at `alpha`, clamped to 0..=1.
+pub fn blend(under: [u8; 3], over: [u8; 3], alpha: f32) -> [u8; 3] {
+    let alpha = alpha.clamp(0.0, 1.0);
+    std::array::from_fn(|i| {
+        (under[i] as f32 + (over[i] as f32 - under[i] as f32) * alpha).round() as u8
+    })
+}
+
+/// Cells drawn on top of the video, one optional slot per terminal cell.
+pub struct Layer {
+    cols: usize,
+    rows: usize,
+    cells: Vec<Option<LayerCell>>,
+}
+
+impl Layer {
+    pub fn new(cols: usize, rows: usize) -> Self {
+        Self {
+            cols,
+            rows,
+            cells: vec![None; cols * rows],
+        }
+    }
+
+    pub fn get(&self, tx: usize, ty: usize) -> Option<LayerCell> {
+        self.cells[ty * self.cols + tx]
+    }
+
+    /// Sets one cell; positions outside the grid are ignored.
+    pub fn put_cell(&mut self, tx: usize, ty: usize, cell: LayerCell) {
+        if tx < self.cols && ty < self.rows {
+            self.cells[ty * self.cols + tx] = Some(cell);
+        }
+    }
+
+    /// Sets one cell to an opaque glyph.
+    pub fn put(&mut self, tx: usize, ty: usize, glyph: char, color: [u8; 3]) {
+        self.put_cell(tx, ty, LayerCell::opaque(glyph, color));
+    }
+
+    /// Darkens the video under a cell by `amount`, keeping whatever is
+    /// drawn there.
+    pub fn dim(&mut self, tx: usize, ty: usize, amount: f32) {
+        if tx >= self.cols || ty >= self.rows {
+            return;
+        }
+        let cell = self.cells[ty * self.cols + tx].get_or_insert(LayerCell {
+            glyph: None,
+            color: [0; 3],
+            alpha: 0.0,
+            dim: 0.0,
+        });
+        cell.dim = cell.dim.max(amount);
+    }
+
+    /// Outlines the inclusive cell rectangle `(x0, y0)..=(x1, y1)`.
+    pub fn draw_box(&mut self, x0: usize, y0: usize, x1: usize, y1: usize, color: [u8; 3]) {
+        for x in x0 + 1..x1 {
+            self.put(x, y0, '─', color);
+            self.put(x, y1, '─', color);
+        }
+        for y in y0 + 1..y1 {
+            self.put(x0, y, '│', color);
+            self.put(x1, y, '│', color);
+        }
+        self.put(x0, y0, '┌', color);
+        self.put(x1, y0, '┐', color);
+        self.put(x0, y1, '└', color);
+        self.put(x1, y1, '┘', color);
+    }
+
+    pub fn draw_text(&mut self, tx: usize, ty: usize, text: &str, color: [u8; 3]) {
+        for (i, glyph) in text.chars().enumerate() {
+            self.put(tx + i, ty, glyph, color);
+        }
+    }
+
+    /// [`Layer::draw_text`] on a backdrop: the video under the text and
+    /// one cell either side of it darkened by `dim`.
+    pub fn draw_label(&mut self, tx: usize, ty: usize, text: &str, color: [u8; 3], dim: f32) {
+        self.draw_text(tx, ty, text, color);
+        let len = text.chars().count();
+        for x in tx.saturating_sub(1)..=tx + len {
+            self.dim(x, ty, dim);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn layer_cells_blend_over_the_video() {
+        let video = ('#', [200, 100, 0], Some([100; 3]));
+        let composite =
+            |cell: LayerCell, opacity| cell.composite(video.0, video.1, video.2, opacity);
+
+        // Opaque glyphs replace the video's color; the background stays.
+        let opaque = LayerCell::opaque('x', [0, 0, 200]);
+        assert_eq!(composite(opaque, 1.0), ('x', [0, 0, 200], Some([100; 3])));
+        assert_eq!(
+            composite(opaque, 0.5),
+            ('x', [100, 50, 100], Some([100; 3]))
+        );
+
+        // A backdrop keeps the glyph and darkens everything under it.
+        let mut layer = Layer::new(4, 1);
+        layer.draw_label(1, 0, "a", [255; 3], 0.5);
+        assert_eq!(layer.get(1, 0).unwrap().glyph, Some('a'));
+        let backdrop = layer.get(2, 0).unwrap();
+        assert_eq!(composite(backdrop, 1.0), ('#', [100, 50, 0], Some([50; 3])));
+        assert!(layer.get(3, 0).is_none());
+    }
+}
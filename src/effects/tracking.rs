@@ -0,0 +1,107 @@
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+};
+
+use super::overlay::{Layer, LayerCell};
+
+/// How many frames of centroid history the trail keeps.
+const TRAIL_LENGTH: usize = 48;
+const TRAIL_COLOR: [u8; 3] = [0, 255, 255];
+
+/// Recent centroids of the largest moving blob, one slot per frame, so the
+/// trail fades at a steady rate even while nothing moves.
+pub struct Trail {
+    points: VecDeque<Option<(usize, usize)>>,
+    grid: (usize, usize),
+}
+
+impl Default for Trail {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Trail {
+    pub fn new() -> Self {
+        Self {
+            points: VecDeque::with_capacity(TRAIL_LENGTH),
+            grid: (0, 0),
+        }
+    }
+
+    /// Records this frame's centroid on a `cols` x `rows` grid. History from
+    /// a different grid size is dropped since its coordinates no longer line
+    /// up.
+    pub fn push(&mut self, point: Option<(usize, usize)>, cols: usize, rows: usize) {
+        if self.grid != (cols, rows) {
+            self.points.clear();
+            self.grid = (cols, rows);
+        }
+        if self.points.len() == TRAIL_LENGTH {
+            self.points.pop_front();
+        }
+        self.points.push_back(point);
+    }
+
+    /// Draws older points fainter, blending further into the video, with
+    /// the newest one as a solid dot.
+    pub fn draw(&self, layer: &mut Layer) {
+        let newest = self.points.len().saturating_sub(1);
+        for (age, point) in self.points.iter().enumerate() {
+            let Some((x, y)) = *point else {
+                continue;
+            };
+            let glyph = if age == newest { '●' } else { '•' };
+            let cell = LayerCell {
+                alpha: (age + 1) as f32 / TRAIL_LENGTH as f32,
+                ..LayerCell::opaque(glyph, TRAIL_COLOR)
+            };
+            layer.put_cell(x, y, cell);
+        }
+    }
+}
+
+/// `--track-log`: the centroids of every frame's moving blobs, largest
+/// first, as CSV rows of `sequence,blob,x,y` in cell coordinates.
+pub struct TrackLog<W: Write> {
+    out: W,
+}
+
+impl<W: Write> TrackLog<W> {
+    /// Starts the log with its header row.
+    pub fn new(mut out: W) -> io::Result<Self> {
+        writeln!(out, "sequence,blob,x,y")?;
+        Ok(Self { out })
+    }
+
+    /// One row per centroid of frame `sequence`; frames without motion
+    /// write nothing.
+    pub fn record(&mut self, sequence: u64, centroids: &[(usize, usize)]) -> io::Result<()> {
+        for (blob, (x, y)) in centroids.iter().enumerate() {
+            writeln!(self.out, "{},{},{},{}", sequence, blob, x, y)?;
+        }
+        Ok(())
+    }
+
+    pub fn flush(&mut self) -> io::Result<()> {
+        self.out.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn log_has_a_row_per_blob() {
+        let mut log = TrackLog::new(Vec::new()).unwrap();
+        log.record(7, &[(10, 4), (2, 3)]).unwrap();
+        log.record(8, &[]).unwrap();
+        log.record(9, &[(11, 4)]).unwrap();
+        assert_eq!(
+            String::from_utf8(log.out).unwrap(),
+            "sequence,blob,x,y\n7,0,10,4\n7,1,2,3\n9,0,11,4\n"
+        );
+    }
+}
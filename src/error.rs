@@ -0,0 +1,252 @@
+//! Error types for everything the library can fail at, grouped by where the
+//! failure happened so callers can match on it.
+
+use std::{io, path::PathBuf};
+
+use nokhwa::NokhwaError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum WebciiError {
+    #[error(transparent)]
+    Camera(#[from] CameraError),
+    #[error(transparent)]
+    Video(#[from] VideoError),
+    #[error(transparent)]
+    Image(#[from] ImageError),
+    #[error(transparent)]
+    Screen(#[from] ScreenError),
+    #[error(transparent)]
+    Pipe(#[from] PipeError),
+    #[error(transparent)]
+    Network(#[from] NetworkError),
+    #[error(transparent)]
+    Terminal(#[from] TerminalError),
+    #[error(transparent)]
+    Render(#[from] RenderError),
+    #[error(transparent)]
+    Sink(#[from] SinkError),
+}
+
+impl WebciiError {
+    /// What the user can do about the error, if there is anything specific.
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            WebciiError::Camera(e) => e.hint(),
+            WebciiError::Video(e) => e.hint(),
+            WebciiError::Screen(e) => e.hint(),
+            WebciiError::Pipe(e) => e.hint(),
+            WebciiError::Network(e) => e.hint(),
+            WebciiError::Terminal(e) => e.hint(),
+            WebciiError::Image(_) | WebciiError::Render(_) | WebciiError::Sink(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum CameraError {
+    #[error("camera access was denied")]
+    PermissionDenied,
+    #[error("could not list cameras: {0}")]
+    Query(#[source] NokhwaError),
+    #[error("could not open camera {index}: {source}")]
+    Open { index: u32, source: NokhwaError },
+    #[error("could not read a frame: {0}")]
+    Frame(#[source] NokhwaError),
+    #[error("could not decode a frame: {0}")]
+    Decode(#[source] NokhwaError),
+}
+
+impl CameraError {
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            CameraError::PermissionDenied => Some(
+                "allow your terminal under System Settings > Privacy & Security > Camera, \
+                 then restart it",
+            ),
+            CameraError::Query(_) => {
+                Some("check that the camera is connected and not in use by another application")
+            }
+            CameraError::Open { .. } => {
+                Some("run `webcii --list-cameras` to see the available cameras and formats")
+            }
+            CameraError::Frame(_) | CameraError::Decode(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum VideoError {
+    #[error("could not run {0}: {1}")]
+    Spawn(&'static str, #[source] io::Error),
+    #[error("could not read video {}: {message}", path.display())]
+    Probe { path: PathBuf, message: String },
+    #[error("could not read a frame from ffmpeg: {0}")]
+    Read(#[source] io::Error),
+}
+
+impl VideoError {
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            VideoError::Spawn(_, e) if e.kind() == io::ErrorKind::NotFound => {
+                Some("video playback needs ffmpeg and ffprobe on your PATH")
+            }
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum ImageError {
+    #[error("could not load image {}: {source}", path.display())]
+    Load {
+        path: PathBuf,
+        source: image::ImageError,
+    },
+    #[error("could not read GIF {}: {source}", path.display())]
+    Gif {
+        path: PathBuf,
+        source: gif::DecodingError,
+    },
+}
+
+/// Failures reaching a network camera. URLs are stored with credentials
+/// removed.
+#[derive(Debug, thiserror::Error)]
+pub enum NetworkError {
+    #[error("can't stream from {0}")]
+    Scheme(String),
+    #[error("could not connect to {url}: {source}")]
+    Connect { url: String, source: VideoError },
+    #[error("lost the stream from {url} and {attempts} reconnects failed: {source}")]
+    Lost {
+        url: String,
+        attempts: u32,
+        source: VideoError,
+    },
+}
+
+impl NetworkError {
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            NetworkError::Scheme(_) => Some("use an rtsp://, rtsps://, http:// or https:// URL"),
+            NetworkError::Connect { source, .. } | NetworkError::Lost { source, .. } => {
+                source.hint().or(Some(
+                    "check the URL and that the camera is reachable from this machine",
+                ))
+            }
+        }
+    }
+}
+
+/// Failures capturing the desktop. The capture library's errors are kept as
+/// text so this type doesn't depend on the optional `screen` feature.
+#[derive(Debug, thiserror::Error)]
+pub enum ScreenError {
+    #[error("this build of webcii has no screen capture")]
+    NotBuilt,
+    #[error("could not list displays: {0}")]
+    Query(String),
+    #[error("there is no display {index}; {count} attached")]
+    NoDisplay { index: usize, count: usize },
+    #[error("could not capture display {index}: {message}")]
+    Capture { index: usize, message: String },
+}
+
+impl ScreenError {
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            ScreenError::NotBuilt => Some("rebuild webcii with `--features screen`"),
+            ScreenError::NoDisplay { .. } => Some("displays are numbered from 0"),
+            ScreenError::Query(_) | ScreenError::Capture { .. } => Some(
+                "on macOS allow your terminal under Privacy & Security > Screen Recording; \
+                 on Wayland accept the desktop's screenshot prompt",
+            ),
+        }
+    }
+}
+
+/// Failures reading frames piped in on stdin.
+#[derive(Debug, thiserror::Error)]
+pub enum PipeError {
+    #[error("unreadable stream on stdin: {0}")]
+    Header(String),
+    #[error("unsupported Y4M {0}")]
+    Unsupported(String),
+    #[error("could not read stdin: {0}")]
+    Read(#[source] io::Error),
+}
+
+impl PipeError {
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            PipeError::Header(_) => Some(
+                "pipe in `ffmpeg ... -f yuv4mpegpipe -`, or raw frames with \
+                 `-f rawvideo -pix_fmt rgb24 -` and `--raw-size WxH`",
+            ),
+            PipeError::Unsupported(_) => Some("add `-pix_fmt yuv420p` to the ffmpeg command"),
+            PipeError::Read(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum TerminalError {
+    #[error("could not set up the terminal: {0}")]
+    Setup(#[source] io::Error),
+    #[error("could not read the terminal size: {0}")]
+    Size(#[source] io::Error),
+}
+
+impl TerminalError {
+    pub fn hint(&self) -> Option<&'static str> {
+        match self {
+            TerminalError::Setup(_) | TerminalError::Size(_) => {
+                Some("webcii needs an interactive terminal; don't redirect its input or output")
+            }
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum RenderError {
+    #[error("cannot render onto an empty {cols}x{rows} grid")]
+    EmptyGrid { cols: usize, rows: usize },
+}
+
+/// Glyph ramps that can't be drawn one glyph per cell.
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+pub enum CharsetError {
+    #[error("a charset needs at least 2 glyphs, got {0}")]
+    TooShort(usize),
+    #[error("a charset has at most 256 glyphs, one per brightness level, got {0}")]
+    TooLong(usize),
+    #[error("{0:?} is a control character")]
+    Control(char),
+    #[error("{0:?} doesn't take a column of its own")]
+    ZeroWidth(char),
+}
+
+/// Failures writing rendered output to wherever it goes.
+#[derive(Debug, thiserror::Error)]
+pub enum SinkError {
+    #[error("could not write the frame: {0}")]
+    Write(#[from] io::Error),
+    #[error("could not encode the frame: {0}")]
+    Encode(#[from] image::ImageError),
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hints_pass_through_the_umbrella_type() {
+        let error = WebciiError::from(CameraError::PermissionDenied);
+        assert_eq!(error.to_string(), "camera access was denied");
+        assert_eq!(error.hint(), CameraError::PermissionDenied.hint());
+        assert!(error.hint().is_some());
+
+        let error = WebciiError::from(RenderError::EmptyGrid { cols: 0, rows: 0 });
+        assert_eq!(error.hint(), None);
+    }
+}
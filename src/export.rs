@@ -0,0 +1,253 @@
+//! `--export-frames`: writes rendered frames to numbered files with a JSON
+//! manifest describing each one.
+
+use std::{
+    error::Error,
+    fs,
+    io::BufWriter,
+    path::{Path, PathBuf},
+    time::Instant,
+};
+
+use clap::ValueEnum;
+use serde::Serialize;
+use webcii::{
+    render::{charset::is_wide, raster, settings::RenderSettings},
+    source::Frame,
+};
+
+use crate::watermark;
+
+#[derive(ValueEnum, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExportFormat {
+    /// The rows rasterized with a bitmap font
+    Png,
+    /// The rows as ANSI art, escapes included
+    Ans,
+}
+
+/// One exported frame in `manifest.json`.
+#[derive(Serialize)]
+struct Entry {
+    /// Counts exported frames from 1, matching the file names.
+    index: u64,
+    /// The source's sequence number for the frame.
+    sequence: u64,
+    source: String,
+    /// When the frame was captured, in milliseconds since export started.
+    timestamp_ms: u64,
+    cols: usize,
+    rows: usize,
+    files: Vec<String>,
+    settings: RenderSettings,
+    /// Hash of the settings line the files are watermarked with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    settings_hash: Option<String>,
+}
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    every: u64,
+    frames: &'a [Entry],
+}
+
+pub struct Exporter {
+    dir: PathBuf,
+    every: u64,
+    formats: Vec<ExportFormat>,
+    started: Instant,
+    rendered: u64,
+    entries: Vec<Entry>,
+    watermark: bool,
+}
+
+impl Exporter {
+    /// Exports every `every`th rendered frame into `dir`, creating it if
+    /// needed.
+    pub fn new(dir: &Path, every: u64, formats: &[ExportFormat]) -> Result<Self, Box<dyn Error>> {
+        fs::create_dir_all(dir).map_err(|e| format!("{}: {}", dir.display(), e))?;
+        Ok(Self {
+            dir: dir.to_path_buf(),
+            every: every.max(1),
+            formats: formats.to_vec(),
+            started: Instant::now(),
+            rendered: 0,
+            entries: Vec::new(),
+            watermark: false,
+        })
+    }
+
+    /// Stamps every file with the settings it was rendered with: a PNG text
+    /// chunk, or a SAUCE comment on ANSI files.
+    pub fn watermark(mut self, watermark: bool) -> Self {
+        self.watermark = watermark;
+        self
+    }
+
+    /// Called with every rendered frame; writes the ones that are due.
+    pub fn frame(
+        &mut self,
+        rows: &[String],
+        frame: &Frame,
+        settings: &RenderSettings,
+    ) -> Result<(), Box<dyn Error>> {
+        self.rendered += 1;
+        if !(self.rendered - 1).is_multiple_of(self.every) {
+            return Ok(());
+        }
+
+        let index = self.entries.len() as u64 + 1;
+        let cols = rows.first().map_or(0, |row| visible_len(row));
+        let line = self.watermark.then(|| watermark::settings_string(settings));
+        let settings_hash = line.as_deref().map(watermark::hash);
+        let mut files = Vec::new();
+        for format in &self.formats {
+            let name = match format {
+                ExportFormat::Png => format!("frame-{:06}.png", index),
+                ExportFormat::Ans => format!("frame-{:06}.ans", index),
+            };
+            let path = self.dir.join(&name);
+            match format {
+                ExportFormat::Png => match (&line, &settings_hash) {
+                    (Some(line), Some(hash)) => {
+                        let text = [(watermark::SETTINGS_KEY, line), (watermark::HASH_KEY, hash)];
+                        save_png(&raster::rasterize(rows), &path, &text)?
+                    }
+                    _ => raster::rasterize(rows).save(&path)?,
+                },
+                ExportFormat::Ans => {
+                    let mut data = ans(rows).into_bytes();
+                    if let (Some(line), Some(hash)) = (&line, &settings_hash) {
+                        let title = format!("webcii {}", hash);
+                        data.extend(watermark::sauce(&title, line, cols, rows.len()));
+                    }
+                    fs::write(&path, data)?
+                }
+            }
+            files.push(name);
+        }
+
+        self.entries.push(Entry {
+            index,
+            sequence: frame.sequence,
+            source: frame.source.to_string(),
+            timestamp_ms: frame
+                .captured_at
+                .saturating_duration_since(self.started)
+                .as_millis() as u64,
+            cols,
+            rows: rows.len(),
+            files,
+            settings: settings.clone(),
+            settings_hash,
+        });
+        Ok(())
+    }
+
+    /// Writes `manifest.json` for everything exported so far.
+    pub fn finish(&self) -> Result<(), Box<dyn Error>> {
+        let manifest = Manifest {
+            every: self.every,
+            frames: &self.entries,
+        };
+        let path = self.dir.join("manifest.json");
+        fs::write(&path, serde_json::to_string_pretty(&manifest)?)
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        Ok(())
+    }
+}
+
+/// Writes `image` as a PNG with `text` as (keyword, text) chunks, in UTF-8
+/// `iTXt` chunks since a charset can be any Unicode.
+fn save_png(
+    image: &image::RgbImage,
+    path: &Path,
+    text: &[(&str, &String)],
+) -> Result<(), Box<dyn Error>> {
+    let file = BufWriter::new(fs::File::create(path)?);
+    let mut encoder = png::Encoder::new(file, image.width(), image.height());
+    encoder.set_color(png::ColorType::Rgb);
+    encoder.set_depth(png::BitDepth::Eight);
+    for (keyword, text) in text {
+        encoder.add_itxt_chunk(keyword.to_string(), text.to_string())?;
+    }
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(image.as_raw())?;
+    writer.finish()?;
+    Ok(())
+}
+
+/// The rows as a standalone ANSI file, each line ending in a reset.
+fn ans(rows: &[String]) -> String {
+    rows.iter()
+        .map(|row| format!("{}\x1b[0m\r\n", row))
+        .collect()
+}
+
+/// Columns a rendered row takes up, not counting escape sequences.
+fn visible_len(row: &str) -> usize {
+    let mut len = 0;
+    let mut in_escape = false;
+    for c in row.chars() {
+        match c {
+            '\x1b' => in_escape = true,
+            '@'..='~' if in_escape && c != '[' => in_escape = false,
+            _ if in_escape => {}
+            _ if is_wide(c) => len += 2,
+            _ => len += 1,
+        }
+    }
+    len
+}
+
+#[cfg(test)]
+mod tests {
+    use webcii::source::ColorSpace;
+
+    use super::*;
+
+    #[test]
+    fn every_nth_frame_is_written_with_a_manifest() {
+        let dir = std::env::temp_dir().join(format!("webcii-export-{}", std::process::id()));
+        let mut exporter = Exporter::new(&dir, 2, &[ExportFormat::Png, ExportFormat::Ans]).unwrap();
+        let rows = vec!["\x1b[38;2;255;0;0mab".to_string()];
+        let frame = Frame::new(image::RgbImage::new(1, 1), "test".into(), ColorSpace::Srgb);
+        for _ in 0..3 {
+            exporter
+                .frame(&rows, &frame, &RenderSettings::default())
+                .unwrap();
+        }
+        exporter.finish().unwrap();
+
+        let manifest: serde_json::Value =
+            serde_json::from_str(&fs::read_to_string(dir.join("manifest.json")).unwrap()).unwrap();
+        let ans = fs::read_to_string(dir.join("frame-000002.ans")).unwrap();
+        let png = image::open(dir.join("frame-000001.png")).unwrap();
+        let _ = fs::remove_dir_all(&dir);
+
+        assert_eq!(manifest["frames"].as_array().unwrap().len(), 2);
+        assert_eq!(manifest["frames"][1]["cols"], 2);
+        assert_eq!(ans, "\x1b[38;2;255;0;0mab\x1b[0m\r\n");
+        assert_eq!(png.width(), 2 * raster::CELL_WIDTH);
+    }
+
+    #[test]
+    fn unicode_charsets_survive_the_png_watermark() {
+        let dir = std::env::temp_dir().join(format!("webcii-watermark-{}", std::process::id()));
+        let mut exporter = Exporter::new(&dir, 1, &[ExportFormat::Png])
+            .unwrap()
+            .watermark(true);
+        let blocks = webcii::render::preset::find("blocks").unwrap().ramp;
+        let settings = RenderSettings {
+            charset: Some(blocks.to_string()),
+            ..RenderSettings::default()
+        };
+        let frame = Frame::new(image::RgbImage::new(1, 1), "test".into(), ColorSpace::Srgb);
+        let written = exporter.frame(&["█▓".to_string()], &frame, &settings);
+        let loaded = watermark::load(&dir.join("frame-000001.png").display().to_string());
+        let _ = fs::remove_dir_all(&dir);
+
+        written.unwrap();
+        assert_eq!(loaded.unwrap().charset.as_deref(), Some(blocks));
+    }
+}
@@ -0,0 +1,186 @@
+//! The performance HUD (`--hud`, or `s` while running): sparklines of the
+//! frame rate, frame time and bytes written per frame over the last
+//! [`COLUMNS`] x [`BUCKET`], drawn over the top-left corner so spikes line
+//! up with whatever was just done, a resize or a toggled filter.
+
+use std::{
+    collections::VecDeque,
+    io::{self, Write},
+    time::{Duration, Instant},
+};
+
+use crossterm::{cursor, queue};
+
+use crate::memory::format_bytes;
+
+/// Frames are averaged over this long into one column of each graph.
+pub const BUCKET: Duration = Duration::from_millis(500);
+/// Columns per graph: 15 seconds of history.
+pub const COLUMNS: usize = 30;
+
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+#[derive(Clone, Copy)]
+struct Sample {
+    fps: f32,
+    frame_ms: f32,
+    bytes: f32,
+}
+
+/// Per-frame stats, averaged into buckets.
+pub struct Hud {
+    history: VecDeque<Sample>,
+    started: Instant,
+    frames: u32,
+    frame_time: Duration,
+    bytes: usize,
+}
+
+impl Default for Hud {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Hud {
+    pub fn new() -> Self {
+        Self {
+            history: VecDeque::with_capacity(COLUMNS),
+            started: Instant::now(),
+            frames: 0,
+            frame_time: Duration::ZERO,
+            bytes: 0,
+        }
+    }
+
+    /// Counts a frame that took `frame_time` and wrote `bytes`, closing the
+    /// bucket once it spans [`BUCKET`].
+    pub fn record(&mut self, frame_time: Duration, bytes: usize) {
+        self.frames += 1;
+        self.frame_time += frame_time;
+        self.bytes += bytes;
+        let elapsed = self.started.elapsed();
+        if elapsed < BUCKET {
+            return;
+        }
+        let frames = self.frames as f32;
+        self.push(Sample {
+            fps: frames / elapsed.as_secs_f32(),
+            frame_ms: self.frame_time.as_secs_f32() * 1000.0 / frames,
+            bytes: self.bytes as f32 / frames,
+        });
+        self.started = Instant::now();
+        self.frames = 0;
+        self.frame_time = Duration::ZERO;
+        self.bytes = 0;
+    }
+
+    fn push(&mut self, sample: Sample) {
+        if self.history.len() == COLUMNS {
+            self.history.pop_front();
+        }
+        self.history.push_back(sample);
+    }
+
+    /// One line per graph, oldest column first, ending with the latest
+    /// value, e.g. `fps   ▇▇▇▂▇▇ 30`. Empty until the first bucket closes.
+    pub fn lines(&self) -> Vec<String> {
+        let Some(latest) = self.history.back() else {
+            return Vec::new();
+        };
+        let graph = |value: fn(&Sample) -> f32| sparkline(self.history.iter().map(value));
+        vec![
+            format!("fps   {:<COLUMNS$} {:.0}", graph(|s| s.fps), latest.fps),
+            format!(
+                "ms    {:<COLUMNS$} {:.1}",
+                graph(|s| s.frame_ms),
+                latest.frame_ms
+            ),
+            format!(
+                "bytes {:<COLUMNS$} {}",
+                graph(|s| s.bytes),
+                format_bytes(latest.bytes as usize)
+            ),
+        ]
+    }
+}
+
+/// `values` as bars scaled to the largest of them.
+pub fn sparkline(values: impl Iterator<Item = f32> + Clone) -> String {
+    let max = values.clone().fold(0.0, f32::max);
+    values
+        .map(|value| {
+            let level = if max > 0.0 {
+                (value / max * (LEVELS.len() - 1) as f32).round() as usize
+            } else {
+                0
+            };
+            LEVELS[level.min(LEVELS.len() - 1)]
+        })
+        .collect()
+}
+
+/// Draws `lines`, the HUD's and any notices under it, from the top-left
+/// corner, one padded line per row.
+/// `plain` leaves the colors out.
+pub fn draw<W: Write>(out: &mut W, lines: &[String], plain: bool) -> io::Result<()> {
+    for (i, line) in lines.iter().enumerate() {
+        queue!(out, cursor::MoveTo(0, i as u16))?;
+        if plain {
+            write!(out, " {} ", line)?;
+        } else {
+            write!(out, "\x1b[0;97;40m {} \x1b[0m", line)?;
+        }
+    }
+    Ok(())
+}
+
+/// Counts the bytes written through it, for the bytes-per-frame graph.
+pub struct Counted<W> {
+    inner: W,
+    pub written: usize,
+}
+
+impl<W> Counted<W> {
+    pub fn new(inner: W) -> Self {
+        Self { inner, written: 0 }
+    }
+}
+
+impl<W: Write> Write for Counted<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.written += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sparklines_scale_to_the_peak() {
+        assert_eq!(sparkline([0.0, 10.0, 40.0, 70.0].into_iter()), "▁▂▅█");
+        assert_eq!(sparkline([0.0, 0.0].into_iter()), "▁▁");
+
+        let mut hud = Hud::new();
+        assert!(hud.lines().is_empty());
+        for fps in 0..COLUMNS + 2 {
+            hud.push(Sample {
+                fps: fps as f32,
+                frame_ms: 12.0,
+                bytes: 40960.0,
+            });
+        }
+        let lines = hud.lines();
+        assert!(lines[0].starts_with("fps   ▁▂"));
+        assert!(lines[0].ends_with("█ 31"));
+        assert_eq!(lines[1], format!("ms    {} 12.0", "█".repeat(COLUMNS)));
+        assert!(lines[2].ends_with(" 40K"));
+    }
+}
@@ -0,0 +1,14 @@
+//! Real-time ASCII rendering of camera frames for the terminal.
+//!
+//! Frames come from a [`source::FrameSource`], [`render::Renderer`] turns
+//! them into rows of colored glyphs, [`effects`] holds the analysis overlays
+//! drawn on top, [`output`] can draw frames as real images instead, [`term`]
+//! owns the terminal and key handling, and [`error`] has the error types they
+//! return.
+
+pub mod effects;
+pub mod error;
+pub mod output;
+pub mod render;
+pub mod source;
+pub mod term;
@@ -1,213 +1,409 @@
-use std::{env::var, io::Write, time::Instant, usize};
+mod cli;
+mod config;
+mod doctor;
+mod export;
+mod hud;
+mod memory;
+mod playback;
+mod session;
+mod supervisor;
+mod watermark;
+
+use std::{
+    error::Error,
+    io::Write,
+    path::Path,
+    process::ExitCode,
+    time::{Duration, Instant},
+};
 
+use clap::{CommandFactory, FromArgMatches};
 use crossterm::{
-    cursor,
-    event::{self, Event, KeyCode, KeyModifiers},
-    execute, queue, terminal,
-};
-use nokhwa::{
-    Camera,
-    pixel_format::RgbFormat,
-    utils::{CameraIndex, RequestedFormat, RequestedFormatType},
+    event::{self, Event},
+    queue, terminal,
 };
-use rayon::iter::{IntoParallelIterator, ParallelIterator};
 use tokio::sync::watch;
+use webcii::{
+    effects::tracking::TrackLog,
+    error::{
+        CameraError, NetworkError, PipeError, ScreenError, TerminalError, VideoError, WebciiError,
+    },
+    output::{Area, Backend},
+    render::{
+        Renderer, ascii, canvas,
+        control::{self, Change},
+        raster,
+        settings::RenderSettings,
+        tone::Tone,
+        write_frame,
+    },
+    source::{
+        self,
+        animation::GifSource,
+        camera::CameraSource,
+        devices, hotplug,
+        idle::IdleCard,
+        network::NetworkSource,
+        pattern::{self, TestPattern},
+        pipe::PipeSource,
+        still::ImageSource,
+        video::VideoSource,
+    },
+    term::{
+        self, INPUT_POLL_INTERVAL, NOTICE_DURATION, TITLE_REFRESH_INTERVAL, TerminalGuard, card,
+        input, query,
+    },
+};
 
-struct TerminalGuard;
+use crate::{
+    cli::{Args, Command, SourceKind},
+    config::Config,
+    export::Exporter,
+    session::{SaveOnExit, Session},
+    supervisor::{Restart, Supervisor},
+};
 
-impl Drop for TerminalGuard {
-    fn drop(&mut self) {
-        let _ = execute!(std::io::stdout(), cursor::Show);
-        let _ = terminal::disable_raw_mode();
+#[tokio::main]
+async fn main() -> ExitCode {
+    match run().await {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("webcii: {}", e);
+            if let Some(hint) = hint(e.as_ref()) {
+                eprintln!("hint: {}", hint);
+            }
+            ExitCode::FAILURE
+        }
     }
 }
 
-const ASCII_CHARS: [char; 70] = [
-    '$', '@', 'B', '%', '8', '&', 'W', 'M', '#', '*', 'o', 'a', 'h', 'k', 'b', 'd', 'p', 'q', 'w',
-    'm', 'Z', 'O', '0', 'Q', 'L', 'C', 'J', 'U', 'Y', 'X', 'z', 'c', 'v', 'u', 'n', 'x', 'r', 'j',
-    'f', 't', '/', '\\', '|', '(', ')', '1', '{', '}', '[', ']', '?', '-', '_', '+', '~', '<', '>',
-    'i', '!', 'l', 'I', ';', ':', ',', '"', '^', '`', '\'', '.', ' ',
-];
-const TARGET_FRAME_TIME_MS: u128 = 16;
-
-enum SobelEdge {
-    None,
-    Horizontal,
-    Vertical,
-    DiagonalUp,
-    DiagonalDown,
-}
-
-struct DecodedFrame {
-    buffer: image::ImageBuffer<image::Rgb<u8>, Vec<u8>>,
-    width: usize,
-    height: usize,
-    pixels: Vec<u8>,
-}
-
-fn sobel_detect_edge(
-    decoded: &image::ImageBuffer<image::Rgb<u8>, Vec<u8>>,
-    x: usize,
-    y: usize,
-    width: usize,
-    height: usize,
-    threshold: f32,
-) -> SobelEdge {
-    if x == 0 || y == 0 || x >= width - 1 || y >= height - 1 {
-        return SobelEdge::None;
+async fn run() -> Result<(), Box<dyn Error>> {
+    let matches = Args::command().get_matches();
+    let args = Args::from_arg_matches(&matches)?;
+    if args.list_cameras {
+        devices::request_camera_permission()?;
+        print!("{}", devices::format_table(&devices::query()?));
+        return Ok(());
     }
-
-    let get_brightness = |px: u32, py: u32| -> i32 {
-        let pixel = decoded.get_pixel(px, py);
-        ((pixel[0] as u32 + pixel[1] as u32 + pixel[2] as u32) / 3) as i32
+    if let Some(Command::Doctor) = &args.command {
+        let checks = doctor::run();
+        print!("{}", doctor::report(&checks));
+        let failed = checks
+            .iter()
+            .filter(|check| check.status == doctor::Status::Fail)
+            .count();
+        return match failed {
+            0 => Ok(()),
+            1 => Err("1 check failed".into()),
+            n => Err(format!("{} checks failed", n).into()),
+        };
+    }
+    let mut config = Config::load(args.config.as_deref())?;
+    let restored = match &args.session {
+        Some(path) => Session::load(path)?,
+        None => None,
     };
-
-    // 3x3 neighborhood
-    let nw = get_brightness((x - 1) as u32, (y - 1) as u32);
-    let n = get_brightness((x) as u32, (y - 1) as u32);
-    let ne = get_brightness((x + 1) as u32, (y - 1) as u32);
-    let w = get_brightness((x - 1) as u32, (y) as u32);
-    let e = get_brightness((x + 1) as u32, (y) as u32);
-    let sw = get_brightness((x - 1) as u32, (y + 1) as u32);
-    let s = get_brightness((x) as u32, (y + 1) as u32);
-    let se = get_brightness((x + 1) as u32, (y + 1) as u32);
-
-    // Sobel operator kernels
-    // Gx (horizontal gradient):     Gy (vertical gradient):
-    //   -1  0  +1                      -1  -2  -1
-    //   -2  0  +2                       0   0   0
-    //   -1  0  +1                      +1  +2  +1
-
-    let gx = -nw + ne - 2 * w + 2 * e - sw + se;
-    let gy = -nw - 2 * n - ne + sw + 2 * s + se;
-
-    let magnitude = ((gx * gx + gy * gy) as f32).sqrt();
-
-    if magnitude <= threshold {
-        return SobelEdge::None;
+    let mut initial_settings = match restored {
+        Some(session) => session.restore(&mut config),
+        None => RenderSettings {
+            charset: config.charset.clone(),
+            ..RenderSettings::default()
+        },
+    };
+    config.apply_args(&args, &matches);
+    if let Some(look) = &args.apply_settings {
+        initial_settings = watermark::load(look)?;
+        initial_settings.honor_no_color();
     }
+    if let Some(mode) = args.mode {
+        initial_settings.mode = mode;
+    }
+    if let Some(color) = args.color {
+        initial_settings.colorizer = color;
+    }
+    if let Some(dither) = args.dither {
+        initial_settings.dither = dither;
+    }
+    if let Some(edge_color) = args.edge_color {
+        initial_settings.edge_color = edge_color;
+    }
+    if let Some(opacity) = args.overlay_opacity {
+        initial_settings.overlay_opacity = opacity;
+    }
+    if let Some(dim) = args.label_dim {
+        initial_settings.label_dim = dim;
+    }
+    if let Some(brightness) = args.brightness {
+        initial_settings.brightness = brightness;
+    }
+    if let Some(contrast) = args.contrast {
+        initial_settings.contrast = contrast;
+    }
+    if args.hud {
+        initial_settings.hud = true;
+    }
+    if args.heatmap {
+        initial_settings.heatmap = true;
+    }
+    if let Some(path) = &args.charset_file {
+        let text =
+            std::fs::read_to_string(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+        // Editors end files with a newline nobody means as a glyph.
+        let text = text.trim_end_matches(['\n', '\r']);
+        cli::parse_charset(text).map_err(|e| format!("{}: {}", path.display(), e))?;
+        config.charset = Some(text.to_string());
+    }
+    if args.charset_file.is_some() || args.charset.is_some() || args.charset_preset.is_some() {
+        initial_settings.charset = config.charset.clone();
+    }
+    let macro_steps = match &args.command {
+        Some(Command::Macro { script }) => {
+            Some(playback::parse(&std::fs::read_to_string(script)?)?)
+        }
+        _ => None,
+    };
+    if let Some(Command::Image { file, once: true }) = &args.command {
+        return print_image(file, &config, &initial_settings);
+    }
+    #[cfg(not(feature = "screen"))]
+    if let Some(Command::Screen { .. }) = &args.command {
+        return Err(ScreenError::NotBuilt.into());
+    }
+    if args.stdin && args.command.is_some() {
+        return Err("--stdin reads frames from stdin and can't be used with a subcommand".into());
+    }
+    let test_pattern = args.source == SourceKind::Test;
+    // The pattern stands in for the camera, so only other sources conflict.
+    let other_source = matches!(
+        args.command,
+        Some(
+            Command::Play { .. }
+                | Command::Image { .. }
+                | Command::Screen { .. }
+                | Command::Stream { .. }
+        )
+    );
+    if test_pattern && (args.stdin || other_source) {
+        return Err(
+            "--source test can't be combined with --stdin, play, image, stream or screen".into(),
+        );
+    }
+    let command = args.command.clone();
+    let live = !args.stdin && !test_pattern && !other_source;
 
-    let angle = (gy as f32).atan2(gx as f32);
-
-    let degrees = angle.to_degrees();
-    let normalized = if degrees < 0.0 {
-        degrees + 360.0
-    } else {
-        degrees
+    let mut exporter = match &args.export_frames {
+        Some(dir) => {
+            Some(Exporter::new(dir, args.every, &args.export_format)?.watermark(args.watermark))
+        }
+        None => None,
     };
 
-    match normalized {
-        a if a >= 337.5 || a < 22.5 => SobelEdge::Vertical,
-        a if a >= 22.5 && a < 67.5 => SobelEdge::DiagonalDown,
-        a if a >= 67.5 && a < 112.5 => SobelEdge::Horizontal,
-        a if a >= 112.5 && a < 157.5 => SobelEdge::DiagonalUp,
-        a if a >= 157.5 && a < 202.5 => SobelEdge::Vertical,
-        a if a >= 202.5 && a < 247.5 => SobelEdge::DiagonalDown,
-        a if a >= 247.5 && a < 292.5 => SobelEdge::Horizontal,
-        _ => SobelEdge::DiagonalUp,
-    }
-}
+    let mut track_log = match &args.track_log {
+        Some(path) => {
+            let file =
+                std::fs::File::create(path).map_err(|e| format!("{}: {}", path.display(), e))?;
+            Some(TrackLog::new(std::io::BufWriter::new(file))?)
+        }
+        None => None,
+    };
 
-#[tokio::main]
-async fn main() -> Result<(), Box<dyn std::error::Error>> {
-    terminal::enable_raw_mode()?;
-    let _guard = TerminalGuard;
+    // Text-only output, exports, the track log and the heatmap export stay
+    // with the glyph renderer, which is what finds motion.
+    let text_only = exporter.is_some() || track_log.is_some() || args.heatmap_export.is_some();
+    let backend = match args.backend {
+        Backend::Auto if config.ascii_only || config.plain || text_only => Backend::Text,
+        Backend::Kitty | Backend::Iterm2 | Backend::Sixel if exporter.is_some() => {
+            return Err("--export-frames writes rendered text and needs the text backend".into());
+        }
+        Backend::Kitty | Backend::Iterm2 | Backend::Sixel if track_log.is_some() => {
+            return Err(
+                "--track-log finds blobs while rendering text and needs the text backend".into(),
+            );
+        }
+        Backend::Kitty | Backend::Iterm2 | Backend::Sixel if args.heatmap_export.is_some() => {
+            return Err(
+                "--heatmap-export counts motion while rendering text and needs the text backend"
+                    .into(),
+            );
+        }
+        backend => backend,
+    };
 
-    let mut stdout = std::io::stdout();
-    execute!(
-        stdout,
-        terminal::Clear(terminal::ClearType::All),
-        cursor::Hide
-    )?;
+    let (settings_tx, mut live_settings) = control::channel(initial_settings);
+    let (camera_tx, camera_rx) = watch::channel(config.camera);
+    // Declared before the terminal guard so it saves after the terminal is
+    // restored, where an error about it can be read.
+    let _session = args
+        .session
+        .as_ref()
+        .map(|path| SaveOnExit::new(path, &config, settings_tx.subscribe(), camera_rx));
+
+    let _guard = TerminalGuard::enter()?;
+    // Detection asks the terminal, so it has to happen in raw mode and before
+    // the input task starts reading.
+    let mut graphics = backend.detect().graphics();
+    let mut stdout = hud::Counted::new(std::io::stdout());
 
     let (frame_tx, mut frame_rx) = watch::channel(None);
     let (quit_tx, mut quit_rx) = watch::channel(false);
+    let (pick_tx, pick_rx) = watch::channel(None);
+    let (waiting_tx, waiting_rx) = watch::channel(None);
+    let (notice_tx, notice_rx) = watch::channel(None::<(String, Instant)>);
+
+    let (supervisor, task_statuses) = Supervisor::new();
+
+    if let Some(steps) = macro_steps {
+        let (settings_tx, quit_tx) = (settings_tx.clone(), quit_tx.clone());
+        let supervisor = supervisor.clone();
+        tokio::spawn(async move {
+            let mut steps = Some(steps);
+            // A macro isn't worth replaying from the top after a panic.
+            supervisor
+                .run("macro", Restart::Never, || {
+                    let play = playback::play(
+                        steps.take().unwrap_or_default(),
+                        settings_tx.clone(),
+                        quit_tx.clone(),
+                    );
+                    tokio::spawn(async move {
+                        play.await;
+                        Ok::<(), std::io::Error>(())
+                    })
+                })
+                .await
+        });
+    }
 
-    tokio::spawn(async move {
-        loop {
-            if let Ok(Event::Key(key)) = event::read() {
-                match key.code {
-                    KeyCode::Char('q') => {
-                        let _ = quit_tx.send(true);
-                        break;
-                    }
-                    KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                        let _ = quit_tx.send(true);
-                        break;
-                    }
-                    _ => {}
-                }
-            }
+    let mut input_task = tokio::spawn({
+        let supervisor = supervisor.clone();
+        async move {
+            supervisor
+                .run("input", Restart::OnFailure { max_restarts: 3 }, || {
+                    let (settings_tx, quit_tx) = (settings_tx.clone(), quit_tx.clone());
+                    let pick_tx = pick_tx.clone();
+                    // Reading keys blocks, so keep it off the async workers,
+                    // and stop once the render loop has dropped its receiver
+                    // so the runtime isn't left waiting on it at exit.
+                    tokio::task::spawn_blocking(move || {
+                        while !quit_tx.is_closed() {
+                            if event::poll(INPUT_POLL_INTERVAL)?
+                                && let Event::Key(key) = event::read()?
+                            {
+                                if let Some(index) = input::camera_choice(key) {
+                                    pick_tx.send_replace(Some(index));
+                                }
+                                if input::handle_key(key, &settings_tx) {
+                                    let _ = quit_tx.send(true);
+                                    break;
+                                }
+                            }
+                        }
+                        Ok::<(), std::io::Error>(())
+                    })
+                })
+                .await
         }
     });
 
     // KNOWN ISSUE: First run may hang on camera initialization
     // This is a hardware/driver warm-up issue, not a Rust problem
     // Workaround: Run twice, or wait ~30s on first run
-    println!("Stream opened. Warming up...");
-    println!("NOTE: First run may take 30s while camera initializes...");
-
-    tokio::task::spawn_blocking(move || {
-        let index = CameraIndex::Index(0);
-        let requested =
-            RequestedFormat::new::<RgbFormat>(RequestedFormatType::AbsoluteHighestFrameRate);
-
-        let mut camera = match Camera::new(index, requested) {
-            Ok(cam) => cam,
-            Err(e) => {
-                eprintln!("Error creating camera: {}", e);
-                return;
-            }
-        };
-
-        if let Err(e) = camera.open_stream() {
-            eprintln!("Error opening stream: {}", e);
-            return;
-        }
+    if live {
+        println!("Stream opened. Warming up...");
+        println!("NOTE: First run may take 30s while camera initializes...");
+    }
 
-        loop {
-            let frame_data = camera.frame();
-
-            if let Ok(frame) = frame_data {
-                match frame.decode_image::<RgbFormat>() {
-                    Ok(decoded) => {
-                        let width = frame.resolution().width() as usize;
-                        let height = frame.resolution().height() as usize;
-                        let pixels = decoded.as_raw().to_vec();
-                        if frame_tx
-                            .send(Some(DecodedFrame {
-                                buffer: decoded,
-                                width,
-                                height,
-                                pixels,
-                            }))
-                            .is_err()
-                        {
-                            break;
+    let camera_index = config.camera;
+    let (stdin, raw_size, fps) = (args.stdin, args.raw_size, config.fps);
+    let resolution = config.resolution();
+    let format_chain = devices::negotiation_chain(config.resolution(), config.fps);
+    let mut capture = tokio::spawn(async move {
+        supervisor
+            .run("capture", Restart::OnFailure { max_restarts: 3 }, || {
+                let (command, format_chain) = (command.clone(), format_chain.clone());
+                let frame_tx = frame_tx.clone();
+                let (mut pick_rx, waiting_tx) = (pick_rx.clone(), waiting_tx.clone());
+                let (notice_tx, camera_tx) = (notice_tx.clone(), camera_tx.clone());
+                tokio::task::spawn_blocking(move || -> Result<(), WebciiError> {
+                    match command {
+                        Some(Command::Play { file, plays }) if is_gif(&file) => {
+                            source::pump(&mut GifSource::open(&file, plays)?, &frame_tx)
+                        }
+                        Some(Command::Play { file, .. }) => {
+                            source::pump(&mut VideoSource::open(&file)?, &frame_tx)
+                        }
+                        Some(Command::Image { file, .. }) => {
+                            source::pump(&mut ImageSource::open(&file)?, &frame_tx)
+                        }
+                        Some(Command::Stream { url }) => {
+                            source::pump(&mut NetworkSource::open(&url)?, &frame_tx)
+                        }
+                        #[cfg(feature = "screen")]
+                        Some(Command::Screen { display }) => {
+                            let mut screen = source::screen::ScreenSource::open(display)?;
+                            source::pump(&mut screen, &frame_tx)
+                        }
+                        _ if test_pattern => {
+                            let (width, height) = resolution.unwrap_or(pattern::DEFAULT_SIZE);
+                            let fps = fps.unwrap_or(pattern::DEFAULT_FPS);
+                            source::pump(&mut TestPattern::new(width, height, fps), &frame_tx)
+                        }
+                        _ if stdin => {
+                            let stdin = std::io::stdin();
+                            match raw_size {
+                                Some((width, height)) => source::pump(
+                                    &mut PipeSource::raw(stdin, width, height, fps),
+                                    &frame_tx,
+                                ),
+                                None => source::pump(&mut PipeSource::y4m(stdin)?, &frame_tx),
+                            }
+                        }
+                        _ => {
+                            devices::request_camera_permission()?;
+                            let devices = hotplug::monitor(devices::list, move |change| {
+                                notice_tx.send_replace(Some((change.to_string(), Instant::now())));
+                            });
+                            // Rather than fail on a missing camera, show the
+                            // test card until it is plugged in. After an
+                            // unplug, any other camera will do.
+                            let mut unplugged = false;
+                            while let Some(index) = IdleCard::new(camera_index, devices.clone())
+                                .failover(unplugged)
+                                .wait(&mut pick_rx, &waiting_tx, &frame_tx)
+                            {
+                                let mut camera = CameraSource::open(index, &format_chain)?
+                                    .ends_when_unplugged(devices.clone());
+                                camera_tx.send_replace(index);
+                                source::pump(&mut camera, &frame_tx);
+                                if frame_tx.is_closed() {
+                                    break;
+                                }
+                                unplugged = true;
+                            }
                         }
                     }
-                    Err(e) => {
-                        eprintln!("Decode error: {}", e);
-                        continue;
-                    }
-                }
-            }
-        }
+                    Ok(())
+                })
+            })
+            .await
     });
 
-    let mut prev_frame: Option<Vec<u8>> = None;
-    let mut frame_buffer = String::with_capacity(2_000_000);
     let mut should_skip_next_frame = false;
-    let mut prev_rows: Option<Vec<String>> = None;
-
-    let color_lookup: Vec<String> = (0..4096)
-        .map(|i| {
-            let r = ((i >> 8) & 0xF) * 17;
-            let g = ((i >> 4) & 0xF) * 17;
-            let b = (i & 0xF) * 17;
-            format!("\x1b[38;2;{};{};{}m", r, g, b)
-        })
-        .collect();
+    let prev_rows: Option<Vec<String>> = None;
+    let mut shown_in = None;
+    let mut card_shown = false;
+    let target_frame_time = Duration::from_millis(config.target_frame_time);
+    let mut title_frames = 0u32;
+    let mut title_updated_at = Instant::now();
+    let mut hud = hud::Hud::new();
+    let mut hud_shown = false;
+    let mut tone = Tone::new();
+
+    let mut budget = args.max_memory.map(memory::Budget::new);
+    let mut renderer = Renderer::new(live_settings.current().colorizer, config.sobel_threshold)
+        .ascii_only(config.ascii_only)
+        .plain(config.plain)
+        .collect_centroids(track_log.is_some());
 
     loop {
         tokio::select! {
@@ -216,129 +412,166 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     break;
                 }
 
-                let (term_cols, term_rows) = terminal::size()?;
-                let term_width = term_cols as usize;
-                let term_height = term_rows as usize;
-                let total_pixels = term_width * term_height;
-                let estimated_size = term_width * term_height * 25;
-                let sobel_sample_rate = if total_pixels > 200_000 {
-                    20
-                } else if total_pixels > 100_000 {
-                    10
-                } else {
-                    1
-                };
-
-                if frame_buffer.capacity() < estimated_size {
-                    frame_buffer.reserve(estimated_size - frame_buffer.capacity());
+                let (term_width, term_height) = term::size()?;
+                if term_width == 0 || term_height == 0 {
+                    continue;
                 }
 
+                if live_settings.update() == Some(Change::Restart) && !config.plain {
+                    // Drop bold/background left over from the previous mode.
+                    write!(stdout, "\x1b[0m")?;
+                }
+                let settings = live_settings.current();
+
                 if let Some(frame) = frame_rx.borrow().as_ref() {
                     let frame_start = Instant::now();
+                    let written_before = stdout.written;
+
+                    // Brightness and contrast go into the frame itself, so
+                    // the text renderer and the image backends both get
+                    // them.
+                    tone.set(settings.brightness, settings.contrast);
+                    let indicator = tone.indicator();
+                    let adjusted = tone.apply(frame);
+                    let frame_bytes = memory::frame_bytes(frame)
+                        + adjusted.map_or(0, memory::frame_bytes);
+                    let frame = adjusted.unwrap_or(frame);
 
                     if should_skip_next_frame {
-                        prev_frame = Some(frame.pixels.clone());
+                        renderer.skip(frame);
                         should_skip_next_frame = false;
                         continue;
                     }
 
-                    frame_buffer.clear();
-
-                    let width = frame.width;
-                    let height = frame.height;
-                    let decoded = &frame.buffer;
-                    let current_pixels = &frame.pixels;
-
-                    let rows: Vec<String> = (0..term_height)
-                        .into_par_iter()
-                        .map(|ty| {
-                            let mut row_buffer = String::with_capacity(term_width * 20);
-
-                            let mut last_color_idx = usize::MAX;
-
-                            for tx in 0..term_width {
-                                let x = tx * width / term_width;
-                                let y = ty * height / term_height;
-                                let pixel = decoded.get_pixel(x as u32, y as u32);
-                                let mut r = pixel[0];
-                                let mut g = pixel[1];
-                                let mut b = pixel[2];
-
-                                if let Some(prev) = &prev_frame {
-                                    let idx = (y * width + x) * 3;
-                                    if idx + 2 < prev.len() {
-                                        r = ((r as u16 * 7 + prev[idx] as u16 * 3) / 10) as u8;
-                                        g = ((g as u16 * 7 + prev[idx + 1] as u16 * 3) / 10) as u8;
-                                        b = ((b as u16 * 7 + prev[idx + 2] as u16 * 3) / 10) as u8;
-                                    }
-                                }
-
-
-                                let should_sample_sobel = (tx % sobel_sample_rate == 0) && (ty % sobel_sample_rate == 0);
-                                let sobel_edge = if should_sample_sobel {
-                                    sobel_detect_edge(&decoded, x, y, width, height, 30.0)
-                                } else {
-                                    SobelEdge::None
-                                };
-
-                                let ascii_char = match sobel_edge {
-                                    SobelEdge::Horizontal => '═',
-                                    SobelEdge::Vertical => '║',
-                                    SobelEdge::DiagonalUp => '/',
-                                    SobelEdge::DiagonalDown => '\\',
-                                    SobelEdge::None => pixel_to_ascii(r, g, b),
-                                };
-
-                                let r_idx = (r / 16) as usize;
-                                let g_idx = (g / 16) as usize;
-                                let b_idx = (b / 16) as usize;
-                                let color_idx = (r_idx << 8) | (g_idx << 4) | b_idx;
-                                if color_idx != last_color_idx {
-                                    row_buffer.push_str(&color_lookup[color_idx]);
-                                    last_color_idx = color_idx;
+                    let graphics_bytes =
+                        graphics.as_ref().map_or(0, |graphics| graphics.memory_usage());
+                    if let Some(budget) = &mut budget {
+                        if !budget.fits(frame_bytes + graphics_bytes + renderer.memory_usage()) {
+                            renderer.flush_caches();
+                        }
+                        let needed = frame_bytes + graphics_bytes + renderer.memory_usage();
+                        if budget.update(needed) {
+                            budget.drop_frame();
+                            // Nothing is rendered, so say why in the title
+                            // right away.
+                            if !config.plain {
+                                let mut title = format!("webcii — {}", budget.summary());
+                                if config.ascii_only {
+                                    title = ascii::fold_str(&title);
                                 }
-                                row_buffer.push(ascii_char);
-                            }
-
-                            row_buffer
-                        })
-                        .collect();
-
-                    // frame_buffer.clear();
-                    // for (i, row) in rows.iter().enumerate() {
-                    //     frame_buffer.push_str(row);
-                    //     if i < term_height - 1 {
-                    //         frame_buffer.push_str("\r\n");
-                    //     }
-                    // }
-
-                    if let Some(prev) = &prev_rows {
-                        for (row_idx, current_row) in rows.iter().enumerate() {
-                            if row_idx >= prev.len() || current_row != &prev[row_idx] {
-                                queue!(stdout, cursor::MoveTo(0, row_idx as u16))?;
-                                write!(stdout, "{}", current_row)?;
+                                queue!(stdout, terminal::SetTitle(title))?;
+                                stdout.flush()?;
                             }
+                            continue;
                         }
-                    } else {
-                        queue!(stdout, cursor::MoveTo(0, 0))?;
+                    }
 
-                        for (i, row) in rows.iter().enumerate() {
-                            write!(stdout, "{}", row)?;
+                    let terminal = (term_width, term_height);
+                    let (cols, rows) = config.canvas(terminal);
+                    let waiting = waiting_rx.borrow().clone();
+                    if card_shown && waiting.is_none() {
+                        queue!(stdout, terminal::Clear(terminal::ClearType::All))?;
+                        card_shown = false;
+                    }
+                    if (config.size.is_some() || graphics.is_some()) && shown_in != Some(terminal)
+                    {
+                        // Don't leave the old frame around a canvas or image
+                        // that no longer covers it.
+                        queue!(stdout, terminal::Clear(terminal::ClearType::All))?;
+                        shown_in = Some(terminal);
+                    }
 
-                            if i < term_height - 1 {
-                                write!(stdout, "\r\n")?;
-                            }
+                    if let Some(graphics) = &mut graphics {
+                        let placement = canvas::place((cols, rows), terminal);
+                        let (origin, (visible_cols, visible_rows)) =
+                            (placement.origin, placement.visible);
+                        // An image reaching the last row would scroll the
+                        // screen.
+                        let last_row = term_height.saturating_sub(1 + origin.1 as usize);
+                        let area = Area {
+                            origin,
+                            cells: (visible_cols, visible_rows.min(last_row).max(1)),
+                            cell_pixels: query::cell_pixels(),
+                        };
+                        graphics.draw(&mut stdout, frame, area)?;
+                    } else {
+                        let rows = renderer.render(frame, cols, rows, settings)?;
+                        if let Some(exporter) = &mut exporter
+                            && waiting.is_none()
+                        {
+                            exporter.frame(&rows, frame, settings)?;
                         }
+                        if let Some(track_log) = &mut track_log {
+                            track_log.record(frame.sequence, renderer.centroids())?;
+                        }
+                        let placement = canvas::place((cols, rows.len()), terminal);
+                        let rows = canvas::crop(&rows, &placement);
+                        write_frame(&mut stdout, &rows, prev_rows.as_deref(), placement.origin)?;
+                    }
+                    if let Some(waiting) = &waiting {
+                        let mut lines = card::lines(waiting);
+                        if config.ascii_only {
+                            lines = lines.iter().map(|line| ascii::fold_str(line)).collect();
+                        }
+                        card::draw(&mut stdout, &lines, terminal, config.plain)?;
+                        card_shown = true;
+                    }
+                    let mut lines = if settings.hud { hud.lines() } else { Vec::new() };
+                    lines.extend(indicator);
+                    if !lines.is_empty() {
+                        if config.ascii_only {
+                            lines = lines.iter().map(|line| ascii::fold_str(line)).collect();
+                        }
+                        hud::draw(&mut stdout, &lines, config.plain)?;
+                        hud_shown = true;
+                    } else if hud_shown {
+                        // Text frames are redrawn in full, but an image
+                        // leaves the HUD's cells alone.
+                        if graphics.is_some() {
+                            queue!(stdout, terminal::Clear(terminal::ClearType::All))?;
+                        }
+                        hud_shown = false;
+                    }
 
+                    if let Some(budget) = &mut budget {
+                        let used = frame_bytes
+                            + renderer.memory_usage()
+                            + graphics.as_ref().map_or(0, |graphics| graphics.memory_usage());
+                        if budget.update(used) {
+                            renderer.flush_caches();
+                        }
                     }
 
+                    title_frames += 1;
+                    let title_elapsed = title_updated_at.elapsed();
+                    if title_elapsed >= TITLE_REFRESH_INTERVAL && !config.plain {
+                        let fps = title_frames as f32 / title_elapsed.as_secs_f32();
+                        let latency = frame.captured_at.elapsed();
+                        let mut title = term::status_title(&frame.source, fps, latency);
+                        if let Some((notice, at)) = notice_rx.borrow().as_ref()
+                            && at.elapsed() < NOTICE_DURATION
+                        {
+                            title = format!("{} — {}", title, notice);
+                        }
+                        if let Some(budget) = &budget {
+                            title = format!("{} {}", title, budget.summary());
+                        }
+                        if let Some(problems) = supervisor::summary(&task_statuses.borrow()) {
+                            title = format!("{} ({})", title, problems);
+                        }
+                        if config.ascii_only {
+                            title = ascii::fold_str(&title);
+                        }
+                        queue!(stdout, terminal::SetTitle(title))?;
+                        title_frames = 0;
+                        title_updated_at = Instant::now();
+                    }
 
                     stdout.flush()?;
 
-                    prev_frame = Some(current_pixels.to_vec());
-
                     let frame_duration = frame_start.elapsed();
-                    should_skip_next_frame = frame_duration.as_millis() > TARGET_FRAME_TIME_MS;
+                    should_skip_next_frame = frame_duration > target_frame_time;
+                    hud.record(frame_duration, stdout.written - written_before);
 
                 }
             },
@@ -347,17 +580,102 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     break;
                 }
             }
+            joined = &mut capture => {
+                // The capture task ends when a video finishes or the source
+                // failed past its restarts.
+                joined??;
+                break;
+            }
+            joined = &mut input_task => {
+                // Without input there is no way to quit, so stop here too.
+                joined??;
+                break;
+            }
         }
     }
 
     stdout.flush()?;
 
+    if let Some(track_log) = &mut track_log {
+        track_log.flush()?;
+    }
+    if let Some(path) = &args.heatmap_export
+        && let Some(heat) = renderer.heatmap().image()
+    {
+        let (width, height) = heat.dimensions();
+        image::imageops::resize(
+            &heat,
+            width * raster::CELL_WIDTH,
+            height * raster::CELL_HEIGHT,
+            image::imageops::FilterType::Nearest,
+        )
+        .save(path)
+        .map_err(|e| format!("{}: {}", path.display(), e))?;
+    }
+    if let Some(exporter) = &exporter {
+        exporter.finish()?;
+    }
     Ok(())
 }
 
-fn pixel_to_ascii(r: u8, g: u8, b: u8) -> char {
-    let brightness = ((r as u32 + g as u32 + b as u32) / 3) as u8;
-    let index = (brightness as usize * ASCII_CHARS.len()) / 256;
+fn is_gif(path: &Path) -> bool {
+    path.extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("gif"))
+}
 
-    ASCII_CHARS[index]
+/// `webcii image --once`: renders the picture at the terminal's size (or
+/// `--size`) and prints it as plain lines, leaving the shell prompt below it.
+fn print_image(
+    path: &Path,
+    config: &Config,
+    settings: &RenderSettings,
+) -> Result<(), Box<dyn Error>> {
+    let frame = ImageSource::open(path)?.frame();
+    let (cols, rows) = match config.size {
+        Some((cols, rows)) => (cols as usize, rows as usize),
+        None => {
+            let (cols, rows) = term::size()?;
+            (cols, rows.saturating_sub(1).max(1))
+        }
+    };
+    let mut tone = Tone::new();
+    tone.set(settings.brightness, settings.contrast);
+    let frame = match tone.apply(&frame) {
+        Some(adjusted) => adjusted,
+        None => &frame,
+    };
+    let mut renderer = Renderer::new(settings.colorizer, config.sobel_threshold)
+        .ascii_only(config.ascii_only)
+        .plain(config.plain);
+    let rows = renderer.render(frame, cols, rows, settings)?;
+
+    let mut stdout = std::io::stdout().lock();
+    for row in rows {
+        let reset = if config.plain { "" } else { "\x1b[0m" };
+        writeln!(stdout, "{}{}", row, reset)?;
+    }
+    Ok(())
+}
+
+/// Remediation hint for a library error, at whichever level it was returned
+/// and however deeply it is wrapped.
+fn hint(e: &(dyn Error + 'static)) -> Option<&'static str> {
+    let direct = if let Some(e) = e.downcast_ref::<WebciiError>() {
+        e.hint()
+    } else if let Some(e) = e.downcast_ref::<CameraError>() {
+        e.hint()
+    } else if let Some(e) = e.downcast_ref::<VideoError>() {
+        e.hint()
+    } else if let Some(e) = e.downcast_ref::<NetworkError>() {
+        e.hint()
+    } else if let Some(e) = e.downcast_ref::<PipeError>() {
+        e.hint()
+    } else if let Some(e) = e.downcast_ref::<ScreenError>() {
+        e.hint()
+    } else if let Some(e) = e.downcast_ref::<TerminalError>() {
+        e.hint()
+    } else {
+        None
+    };
+    direct.or_else(|| e.source().and_then(hint))
 }
@@ -0,0 +1,92 @@
+//! `--max-memory`: a cap on the buffers webcii holds on to between frames.
+//! Render caches are flushed when they push past it, and frames too big to
+//! render under it are dropped.
+
+use webcii::source::Frame;
+
+/// Bytes a frame holds: the image, and the copy kept for comparing it with
+/// the next one.
+pub fn frame_bytes(frame: &Frame) -> usize {
+    frame.buffer.as_raw().capacity() + frame.pixels.capacity()
+}
+
+/// `bytes` in the largest binary unit that keeps it at least 1, e.g. `256M`
+/// or `1.5G`.
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "K", "M", "G"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 || value >= 10.0 || value.fract() == 0.0 {
+        format!("{:.0}{}", value, UNITS[unit])
+    } else {
+        format!("{:.1}{}", value, UNITS[unit])
+    }
+}
+
+pub struct Budget {
+    cap: usize,
+    used: usize,
+    dropped: u64,
+}
+
+impl Budget {
+    pub fn new(cap: usize) -> Self {
+        Self {
+            cap,
+            used: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Whether `bytes` stay under the cap.
+    pub fn fits(&self, bytes: usize) -> bool {
+        bytes <= self.cap
+    }
+
+    /// Counts a frame that wasn't rendered because it didn't fit.
+    pub fn drop_frame(&mut self) {
+        self.dropped += 1;
+    }
+
+    /// Records what is held after a frame, and whether that is over the cap.
+    pub fn update(&mut self, used: usize) -> bool {
+        self.used = used;
+        !self.fits(used)
+    }
+
+    /// Usage for the title, e.g. `mem 12M/256M, 3 dropped`.
+    pub fn summary(&self) -> String {
+        let mut summary = format!("mem {}/{}", format_bytes(self.used), format_bytes(self.cap));
+        if self.dropped > 0 {
+            summary.push_str(&format!(", {} dropped", self.dropped));
+        }
+        summary
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sizes_are_shown_in_binary_units() {
+        assert_eq!(format_bytes(300), "300B");
+        assert_eq!(format_bytes(256 << 20), "256M");
+        assert_eq!(format_bytes(3 << 29), "1.5G");
+        assert_eq!(format_bytes(12_345_678), "12M");
+    }
+
+    #[test]
+    fn summary_counts_dropped_frames() {
+        let mut budget = Budget::new(256 << 20);
+        assert!(!budget.update(12 << 20));
+        assert_eq!(budget.summary(), "mem 12M/256M");
+        assert!(budget.update(300 << 20));
+        budget.drop_frame();
+        assert_eq!(budget.summary(), "mem 300M/256M, 1 dropped");
+    }
+}
@@ -0,0 +1,104 @@
+//! iTerm2 inline images (OSC 1337, also understood by WezTerm and
+//! mintty): each frame goes over as a base64 PNG.
+
+use std::{
+    io::Write,
+    time::{Duration, Instant},
+};
+
+use crossterm::{QueueableCommand, cursor};
+use image::{
+    ExtendedColorType, ImageEncoder,
+    codecs::png::{CompressionType, FilterType, PngEncoder},
+    imageops,
+};
+
+use super::{Area, Graphics, base64, fit};
+use crate::{error::SinkError, source::Frame};
+
+/// Shortest time between two frames. iTerm2 decodes every image on its main
+/// thread, and frames sent faster than it keeps up pile up behind each
+/// other.
+pub const MIN_FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Draws frames as iTerm2 inline images, at most one per
+/// [`MIN_FRAME_INTERVAL`], keeping its buffers between frames.
+#[derive(Default)]
+pub struct Iterm2 {
+    png: Vec<u8>,
+    buffer: Vec<u8>,
+    last_drawn: Option<Instant>,
+}
+
+impl Graphics for Iterm2 {
+    fn draw(&mut self, out: &mut dyn Write, frame: &Frame, area: Area) -> Result<(), SinkError> {
+        if self
+            .last_drawn
+            .is_some_and(|at| at.elapsed() < MIN_FRAME_INTERVAL)
+        {
+            return Ok(());
+        }
+        self.last_drawn = Some(Instant::now());
+
+        let (origin, (width, height)) = fit(frame.width, frame.height, area);
+        let image = imageops::resize(&frame.buffer, width, height, imageops::FilterType::Triangle);
+        self.png.clear();
+        // Fast compression: the PNG only lives until iTerm2 decodes it.
+        PngEncoder::new_with_quality(&mut self.png, CompressionType::Fast, FilterType::Sub)
+            .write_image(image.as_raw(), width, height, ExtendedColorType::Rgb8)?;
+
+        self.buffer.clear();
+        inline_image(&self.png, (width, height), &mut self.buffer);
+        out.queue(cursor::MoveTo(origin.0, origin.1))?;
+        out.write_all(&self.buffer)?;
+        Ok(())
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.png.capacity() + self.buffer.capacity()
+    }
+}
+
+/// Appends the sequence that shows `png` at the cursor, `size` pixels wide
+/// and high, without moving the cursor.
+pub fn inline_image(png: &[u8], size: (u32, u32), out: &mut Vec<u8>) {
+    let _ = write!(
+        out,
+        "\x1b]1337;File=inline=1;size={};width={}px;height={}px;preserveAspectRatio=1;doNotMoveCursor=1:",
+        png.len(),
+        size.0,
+        size.1
+    );
+    out.extend(base64(png));
+    out.push(b'\x07');
+}
+
+#[cfg(test)]
+mod tests {
+    use image::RgbImage;
+
+    use super::*;
+    use crate::source::ColorSpace;
+
+    #[test]
+    fn frames_are_throttled() {
+        let frame = Frame::new(RgbImage::new(4, 4), "test".into(), ColorSpace::Srgb);
+        let area = Area {
+            origin: (0, 0),
+            cells: (1, 1),
+            cell_pixels: (4, 4),
+        };
+        let mut iterm2 = Iterm2::default();
+        let mut out = Vec::new();
+        iterm2.draw(&mut out, &frame, area).unwrap();
+        let text = String::from_utf8(out.clone()).unwrap();
+        assert!(text.contains("\x1b]1337;File=inline=1;size="));
+        assert!(text.contains(";width=4px;height=4px;"));
+        assert!(text.ends_with('\x07'));
+
+        // Right away again: nothing.
+        out.clear();
+        iterm2.draw(&mut out, &frame, area).unwrap();
+        assert!(out.is_empty());
+    }
+}
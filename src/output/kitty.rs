@@ -0,0 +1,120 @@
+//! The kitty graphics protocol (kitty, WezTerm, Ghostty, Konsole): frames
+//! go over as raw RGB in base64 chunks and are shown at full color.
+
+use std::io::Write;
+
+use crossterm::{QueueableCommand, cursor};
+use image::{RgbImage, imageops};
+
+use super::{Area, Graphics, base64, fit};
+use crate::{error::SinkError, source::Frame};
+
+/// Most base64 bytes one escape sequence may carry.
+const CHUNK: usize = 4096;
+
+/// Image ids the frames alternate between: each frame is placed under the
+/// id the previous one isn't using, which is then deleted, so the old frame
+/// stays up until the new one covers it and the terminal never holds more
+/// than two.
+const IDS: [u32; 2] = [1, 2];
+
+/// Draws frames as kitty graphics images, keeping its buffers between
+/// frames.
+#[derive(Default)]
+pub struct Kitty {
+    buffer: Vec<u8>,
+    /// Index in [`IDS`] of the image on screen, once there is one.
+    shown: Option<usize>,
+}
+
+impl Graphics for Kitty {
+    fn draw(&mut self, out: &mut dyn Write, frame: &Frame, area: Area) -> Result<(), SinkError> {
+        let (origin, (width, height)) = fit(frame.width, frame.height, area);
+        let image = imageops::resize(&frame.buffer, width, height, imageops::FilterType::Triangle);
+        let next = self.shown.map_or(0, |shown| 1 - shown);
+
+        self.buffer.clear();
+        transmit(&image, IDS[next], &mut self.buffer);
+        if let Some(shown) = self.shown {
+            delete(IDS[shown], &mut self.buffer);
+        }
+        self.shown = Some(next);
+
+        out.queue(cursor::MoveTo(origin.0, origin.1))?;
+        out.write_all(&self.buffer)?;
+        Ok(())
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.buffer.capacity()
+    }
+}
+
+/// Appends the sequences that transmit `image` as image `id` and place it
+/// at the cursor, without moving the cursor or asking for a reply.
+pub fn transmit(image: &RgbImage, id: u32, out: &mut Vec<u8>) {
+    let data = base64(image.as_raw());
+    let mut chunks = data.chunks(CHUNK).peekable();
+    let mut first = true;
+    while let Some(chunk) = chunks.next() {
+        let more = chunks.peek().is_some() as u8;
+        if first {
+            let _ = write!(
+                out,
+                "\x1b_Ga=T,f=24,s={},v={},i={},C=1,q=2,m={};",
+                image.width(),
+                image.height(),
+                id,
+                more
+            );
+            first = false;
+        } else {
+            let _ = write!(out, "\x1b_Gm={};", more);
+        }
+        out.extend_from_slice(chunk);
+        out.extend_from_slice(b"\x1b\\");
+    }
+}
+
+/// Appends the sequence that deletes image `id` and frees its data.
+pub fn delete(id: u32, out: &mut Vec<u8>) {
+    let _ = write!(out, "\x1b_Ga=d,d=I,i={},q=2\x1b\\", id);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::source::ColorSpace;
+
+    #[test]
+    fn large_images_are_sent_in_chunks() {
+        // 3 * 4096 bytes of pixels make 16384 base64 bytes: four chunks.
+        let image = RgbImage::new(64, 64);
+        let mut out = Vec::new();
+        transmit(&image, 2, &mut out);
+        let text = String::from_utf8(out).unwrap();
+        assert!(text.starts_with("\x1b_Ga=T,f=24,s=64,v=64,i=2,C=1,q=2,m=1;AAAA"));
+        assert_eq!(text.matches("\x1b_Gm=1;").count(), 2);
+        assert_eq!(text.matches("\x1b_Gm=0;").count(), 1);
+        assert!(text.ends_with("AAAA\x1b\\"));
+    }
+
+    #[test]
+    fn each_frame_replaces_the_last() {
+        let frame = Frame::new(RgbImage::new(4, 4), "test".into(), ColorSpace::Srgb);
+        let area = Area {
+            origin: (0, 0),
+            cells: (1, 1),
+            cell_pixels: (4, 4),
+        };
+        let mut kitty = Kitty::default();
+        let mut out = Vec::new();
+        kitty.draw(&mut out, &frame, area).unwrap();
+        assert!(!String::from_utf8_lossy(&out).contains("a=d"));
+        out.clear();
+        kitty.draw(&mut out, &frame, area).unwrap();
+        let text = String::from_utf8_lossy(&out);
+        assert!(text.contains("i=2,"));
+        assert!(text.ends_with("\x1b_Ga=d,d=I,i=1,q=2\x1b\\"));
+    }
+}
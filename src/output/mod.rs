@@ -0,0 +1,169 @@
+//! Output backends: the glyph renderer, or a terminal graphics protocol that
+//! draws frames as real images and bypasses the glyph mapping entirely.
+
+pub mod iterm2;
+pub mod kitty;
+pub mod sixel;
+
+use std::{io::Write, str::FromStr};
+
+use crate::{error::SinkError, source::Frame, term::query};
+
+/// How frames are put on the terminal.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backend {
+    /// The best graphics protocol the terminal says it supports, text if
+    /// none.
+    #[default]
+    Auto,
+    /// Glyphs from [`crate::render::Renderer`].
+    Text,
+    Kitty,
+    /// iTerm2's inline images (OSC 1337).
+    Iterm2,
+    Sixel,
+}
+
+impl Backend {
+    /// Settles [`Backend::Auto`] by asking the terminal what it supports.
+    pub fn detect(self) -> Self {
+        match self {
+            Backend::Auto => {
+                // Kitty and iTerm2 images are full color; sixel only has
+                // the cube.
+                let support = query::graphics_support();
+                if support.kitty {
+                    Backend::Kitty
+                } else if support.iterm2 {
+                    Backend::Iterm2
+                } else if support.sixel {
+                    Backend::Sixel
+                } else {
+                    Backend::Text
+                }
+            }
+            backend => backend,
+        }
+    }
+
+    /// The graphics protocol for the backend; `None` for text, which goes
+    /// through the renderer instead.
+    pub fn graphics(self) -> Option<Box<dyn Graphics>> {
+        match self {
+            Backend::Kitty => Some(Box::new(kitty::Kitty::default())),
+            Backend::Iterm2 => Some(Box::new(iterm2::Iterm2::default())),
+            Backend::Sixel => Some(Box::new(sixel::Sixel::default())),
+            Backend::Auto | Backend::Text => None,
+        }
+    }
+}
+
+impl FromStr for Backend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "auto" => Ok(Backend::Auto),
+            "text" => Ok(Backend::Text),
+            "kitty" => Ok(Backend::Kitty),
+            "iterm2" => Ok(Backend::Iterm2),
+            "sixel" => Ok(Backend::Sixel),
+            _ => Err(format!(
+                "unknown backend `{}`; expected auto, text, kitty, iterm2 or sixel",
+                s
+            )),
+        }
+    }
+}
+
+/// The part of the terminal an image may cover.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Area {
+    /// Top-left cell, as (column, row).
+    pub origin: (u16, u16),
+    /// Size in cells, as (columns, rows).
+    pub cells: (usize, usize),
+    /// Size of one cell in pixels; see [`query::cell_pixels`].
+    pub cell_pixels: (u32, u32),
+}
+
+/// A terminal graphics protocol.
+pub trait Graphics: Send {
+    /// Draws `frame` scaled to fit `area`, keeping its aspect ratio, and
+    /// centered in it.
+    fn draw(&mut self, out: &mut dyn Write, frame: &Frame, area: Area) -> Result<(), SinkError>;
+
+    /// Bytes of buffers kept between frames.
+    fn memory_usage(&self) -> usize;
+}
+
+/// Where a `width` x `height` image scaled to fit `area` goes: its top-left
+/// cell, and its size in pixels.
+pub fn fit(width: usize, height: usize, area: Area) -> ((u16, u16), (u32, u32)) {
+    let (cell_width, cell_height) = area.cell_pixels;
+    let max_width = area.cells.0 as u32 * cell_width;
+    let max_height = area.cells.1 as u32 * cell_height;
+    let (width, height) = (width.max(1) as u64, height.max(1) as u64);
+
+    // Whichever side hits the edge of the area first sets the scale.
+    let (pixels_width, pixels_height) = if width * max_height as u64 > height * max_width as u64 {
+        (max_width, (height * max_width as u64 / width) as u32)
+    } else {
+        ((width * max_height as u64 / height) as u32, max_height)
+    };
+    let (pixels_width, pixels_height) = (pixels_width.max(1), pixels_height.max(1));
+
+    let used_cols = pixels_width.div_ceil(cell_width) as usize;
+    let used_rows = pixels_height.div_ceil(cell_height) as usize;
+    let origin = (
+        area.origin.0 + (area.cells.0.saturating_sub(used_cols) / 2) as u16,
+        area.origin.1 + (area.cells.1.saturating_sub(used_rows) / 2) as u16,
+    );
+    (origin, (pixels_width, pixels_height))
+}
+
+/// Standard base64 with padding.
+fn base64(data: &[u8]) -> Vec<u8> {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut encoded = Vec::with_capacity(data.len().div_ceil(3) * 4);
+    for group in data.chunks(3) {
+        let bytes = [
+            group[0],
+            *group.get(1).unwrap_or(&0),
+            *group.get(2).unwrap_or(&0),
+        ];
+        let bits = u32::from_be_bytes([0, bytes[0], bytes[1], bytes[2]]);
+        for i in 0..4 {
+            if i <= group.len() {
+                encoded.push(ALPHABET[(bits >> (18 - 6 * i) & 0x3f) as usize]);
+            } else {
+                encoded.push(b'=');
+            }
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn base64_pads_partial_groups() {
+        assert_eq!(base64(b"Man"), b"TWFu");
+        assert_eq!(base64(b"Ma"), b"TWE=");
+        assert_eq!(base64(b"M"), b"TQ==");
+    }
+
+    #[test]
+    fn wide_image_is_letterboxed_and_tall_one_pillarboxed() {
+        let area = Area {
+            origin: (0, 0),
+            cells: (80, 24),
+            cell_pixels: (8, 16),
+        };
+        // 640x384 pixels of room.
+        assert_eq!(fit(1280, 360, area), ((0, 6), (640, 180)));
+        assert_eq!(fit(100, 200, area), ((28, 0), (192, 384)));
+    }
+}
@@ -0,0 +1,141 @@
+//! Sixel graphics (DEC, supported by foot, mlterm, WezTerm, xterm in vt340
+//! mode and others), drawn with a fixed 6x6x6 color cube.
+
+use std::io::Write;
+
+use crossterm::{QueueableCommand, cursor};
+use image::{RgbImage, imageops};
+
+use super::{Area, Graphics, fit};
+use crate::{error::SinkError, source::Frame};
+
+/// Levels per channel of the color cube.
+const LEVELS: u32 = 6;
+const COLORS: usize = (LEVELS * LEVELS * LEVELS) as usize;
+
+/// Draws frames as sixel images, keeping its buffers between frames.
+#[derive(Default)]
+pub struct Sixel {
+    buffer: Vec<u8>,
+}
+
+impl Graphics for Sixel {
+    fn draw(&mut self, out: &mut dyn Write, frame: &Frame, area: Area) -> Result<(), SinkError> {
+        let (origin, (width, height)) = fit(frame.width, frame.height, area);
+        let image = imageops::resize(&frame.buffer, width, height, imageops::FilterType::Triangle);
+        self.buffer.clear();
+        encode(&image, &mut self.buffer);
+        out.queue(cursor::MoveTo(origin.0, origin.1))?;
+        out.write_all(&self.buffer)?;
+        Ok(())
+    }
+
+    fn memory_usage(&self) -> usize {
+        self.buffer.capacity()
+    }
+}
+
+/// Appends `image` to `out` as a complete sixel sequence.
+pub fn encode(image: &RgbImage, out: &mut Vec<u8>) {
+    let (width, height) = (image.width() as usize, image.height() as usize);
+    // P2 = 1: pixels no color touches are left as they are.
+    let _ = write!(out, "\x1bP0;1q\"1;1;{};{}", width, height);
+    for index in 0..COLORS as u32 {
+        let [r, g, b] = [index / 36, index / 6 % 6, index % 6].map(|level| level * 100 / 5);
+        let _ = write!(out, "#{};2;{};{};{}", index, r, g, b);
+    }
+
+    let indices: Vec<u8> = image.pixels().map(|pixel| cube_index(pixel.0)).collect();
+    // One row of sixels per color that shows up in the band.
+    let mut slots = [usize::MAX; COLORS];
+    let mut used: Vec<u8> = Vec::new();
+    let mut columns: Vec<Vec<u8>> = Vec::new();
+    for band in (0..height).step_by(6) {
+        for &color in &used {
+            slots[color as usize] = usize::MAX;
+        }
+        used.clear();
+        for dy in 0..6.min(height - band) {
+            let row = &indices[(band + dy) * width..(band + dy + 1) * width];
+            for (x, &color) in row.iter().enumerate() {
+                let slot = &mut slots[color as usize];
+                if *slot == usize::MAX {
+                    *slot = used.len();
+                    used.push(color);
+                    if columns.len() < used.len() {
+                        columns.push(vec![0; width]);
+                    }
+                    columns[*slot].fill(0);
+                }
+                columns[*slot][x] |= 1 << dy;
+            }
+        }
+
+        for (slot, &color) in used.iter().enumerate() {
+            let _ = write!(out, "#{}", color);
+            push_runs(out, &columns[slot]);
+            out.push(b'$');
+        }
+        out.push(b'-');
+    }
+    out.extend_from_slice(b"\x1b\\");
+}
+
+/// The cube color closest to `rgb`.
+fn cube_index([r, g, b]: [u8; 3]) -> u8 {
+    let level = |channel: u8| (channel as u32 * (LEVELS - 1) + 127) / 255;
+    (level(r) * 36 + level(g) * 6 + level(b)) as u8
+}
+
+/// One color's sixels for a band, run-length encoded, without the blank
+/// tail.
+fn push_runs(out: &mut Vec<u8>, bits: &[u8]) {
+    let end = bits.iter().rposition(|&b| b != 0).map_or(0, |i| i + 1);
+    let mut x = 0;
+    while x < end {
+        let run = bits[x..end].iter().take_while(|&&b| b == bits[x]).count();
+        let sixel = b'?' + bits[x];
+        if run > 3 {
+            let _ = write!(out, "!{}", run);
+            out.push(sixel);
+        } else {
+            out.extend(std::iter::repeat_n(sixel, run));
+        }
+        x += run;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgb;
+
+    use super::*;
+
+    #[test]
+    fn image_is_encoded_in_bands_per_color() {
+        // Red over blue in the first column, red alone in the second.
+        let image = RgbImage::from_fn(2, 2, |x, y| {
+            Rgb(if y == 1 && x == 0 {
+                [0, 0, 255]
+            } else {
+                [255, 0, 0]
+            })
+        });
+        let mut out = Vec::new();
+        encode(&image, &mut out);
+        let out = String::from_utf8(out).unwrap();
+
+        assert!(out.starts_with("\x1bP0;1q\"1;1;2;2#0;2;0;0;0#1;2;0;0;20"));
+        assert!(out.contains("#180;2;100;0;0#181"));
+        // Red: the top of column 0 (bit 0) and all of column 1 (bits 0-1);
+        // blue: the bottom of column 0 (bit 1).
+        assert!(out.ends_with("#180@B$#5A$-\x1b\\"));
+    }
+
+    #[test]
+    fn long_runs_are_compressed() {
+        let mut out = Vec::new();
+        push_runs(&mut out, &[1, 1, 1, 1, 1, 2, 0, 0]);
+        assert_eq!(out, b"!5@A");
+    }
+}
@@ -0,0 +1,210 @@
+//! Timed macro scripts for `webcii macro`.
+//!
+//! One step per line, `#` outside a quoted value starts a comment:
+//!
+//! ```text
+//! wait 1500                 # milliseconds
+//! key z                     # same as pressing 'z'
+//! key ctrl-c
+//! set colorizer = "ansi8"   # any RenderSettings field, TOML value syntax
+//! ```
+
+use std::{error::Error, time::Duration};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tokio::sync::watch;
+
+use webcii::{render::settings::RenderSettings, term::input};
+
+#[derive(Debug)]
+pub enum Step {
+    Wait(Duration),
+    Key(KeyEvent),
+    Set(toml::Table),
+}
+
+pub fn parse(script: &str) -> Result<Vec<Step>, Box<dyn Error>> {
+    let mut steps = Vec::new();
+
+    for (number, line) in script.lines().enumerate() {
+        let line = strip_comment(line).trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (command, rest) = line.split_once(' ').unwrap_or((line, ""));
+        let rest = rest.trim();
+        let step = match command {
+            "wait" => rest
+                .parse()
+                .map(|ms| Step::Wait(Duration::from_millis(ms)))
+                .ok(),
+            "key" => parse_key(rest).map(Step::Key),
+            "set" => match rest.parse::<toml::Table>() {
+                // Checked against the defaults now, so a typo fails here
+                // rather than doing nothing halfway through the run.
+                Ok(values) => match merge(&RenderSettings::default(), values.clone()) {
+                    Ok(_) => Some(Step::Set(values)),
+                    Err(e) => return Err(format!("macro line {}: {}", number + 1, e).into()),
+                },
+                Err(_) => None,
+            },
+            _ => None,
+        };
+
+        match step {
+            Some(step) => steps.push(step),
+            None => {
+                return Err(format!("macro line {}: cannot parse `{}`", number + 1, line).into());
+            }
+        }
+    }
+
+    Ok(steps)
+}
+
+/// `line` up to the first `#` that isn't inside a quoted TOML string.
+fn strip_comment(line: &str) -> &str {
+    let (mut quote, mut escaped) = (None, false);
+    for (i, c) in line.char_indices() {
+        match (quote, c) {
+            _ if escaped => escaped = false,
+            (Some('"'), '\\') => escaped = true,
+            (Some(open), _) if c == open => quote = None,
+            (None, '"' | '\'') => quote = Some(c),
+            (None, '#') => return &line[..i],
+            _ => {}
+        }
+    }
+    line
+}
+
+fn parse_key(key: &str) -> Option<KeyEvent> {
+    let (modifiers, key) = match key.strip_prefix("ctrl-") {
+        Some(key) => (KeyModifiers::CONTROL, key),
+        None => (KeyModifiers::NONE, key),
+    };
+
+    let code = match key {
+        "space" => KeyCode::Char(' '),
+        "enter" => KeyCode::Enter,
+        "esc" => KeyCode::Esc,
+        "tab" => KeyCode::Tab,
+        _ => {
+            let mut chars = key.chars();
+            match (chars.next(), chars.next()) {
+                (Some(c), None) => KeyCode::Char(c),
+                _ => return None,
+            }
+        }
+    };
+
+    Some(KeyEvent::new(code, modifiers))
+}
+
+/// Runs the steps against the live settings, as if typed on the keyboard.
+pub async fn play(
+    steps: Vec<Step>,
+    settings: watch::Sender<RenderSettings>,
+    quit: watch::Sender<bool>,
+) {
+    for step in steps {
+        match step {
+            Step::Wait(duration) => tokio::time::sleep(duration).await,
+            Step::Key(key) => {
+                if input::handle_key(key, &settings) {
+                    let _ = quit.send(true);
+                    return;
+                }
+            }
+            Step::Set(values) => {
+                settings.send_if_modified(|current| match merge(current, values) {
+                    Ok(updated) => {
+                        *current = updated;
+                        true
+                    }
+                    // `parse` already checked the values.
+                    Err(_) => false,
+                });
+            }
+        }
+    }
+}
+
+// Round-trips the settings through TOML so `set` accepts exactly the keys
+// and value syntax of a session file. Keys are checked against JSON, which
+// unlike TOML keeps the fields that are unset.
+fn merge(current: &RenderSettings, values: toml::Table) -> Result<RenderSettings, String> {
+    let Ok(serde_json::Value::Object(known)) = serde_json::to_value(current) else {
+        unreachable!("settings serialize to an object");
+    };
+    let mut table = toml::Table::try_from(current).map_err(|e| e.to_string())?;
+    for (key, value) in values {
+        if !known.contains_key(&key) {
+            return Err(format!("unknown setting `{}`", key));
+        }
+        table.insert(key, value);
+    }
+    let settings: RenderSettings = table
+        .try_into()
+        .map_err(|e: toml::de::Error| e.message().to_string())?;
+    settings.validate()?;
+    Ok(settings)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_steps_and_comments() {
+        let steps =
+            parse("# demo\nwait 250\nkey z  # zebras\nkey ctrl-c\nset zebra = false\n").unwrap();
+        assert!(matches!(steps[0], Step::Wait(d) if d == Duration::from_millis(250)));
+        assert!(matches!(steps[1], Step::Key(k) if k.code == KeyCode::Char('z')));
+        assert!(matches!(steps[2], Step::Key(k) if k.modifiers == KeyModifiers::CONTROL));
+        assert!(matches!(&steps[3], Step::Set(t) if t.contains_key("zebra")));
+    }
+
+    #[test]
+    fn reports_bad_lines() {
+        let err = parse("wait 10\nkey too-long\n").unwrap_err();
+        assert!(err.to_string().contains("line 2"));
+        let err = parse("wait 10\nset zebar = true\n").unwrap_err();
+        assert_eq!(err.to_string(), "macro line 2: unknown setting `zebar`");
+        assert!(parse("set mode = \"sideways\"").is_err());
+        let err = parse("set peaking_color = 9").unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "macro line 1: peaking_color 9 out of range; expected 0 to 3"
+        );
+    }
+
+    #[test]
+    fn hashes_in_quoted_values_are_not_comments() {
+        let steps = parse(
+            "set charset = \" .:-=+*#%@\"  # ten levels\nset edge_color = '#ff8800'\nset edge_color = \"#ff8800\"\n",
+        )
+        .unwrap();
+        let Step::Set(charset) = &steps[0] else {
+            panic!("expected set");
+        };
+        assert_eq!(charset["charset"].as_str(), Some(" .:-=+*#%@"));
+        let merged = steps[1..]
+            .iter()
+            .fold(RenderSettings::default(), |current, step| {
+                let Step::Set(values) = step else {
+                    panic!("expected set");
+                };
+                merge(&current, values.clone()).unwrap()
+            });
+        assert!(merged.edge_color == webcii::render::settings::EdgeColor::Fixed([255, 136, 0]));
+    }
+
+    #[test]
+    fn set_merges_into_settings() {
+        let values: toml::Table = "view = \"false-color\"".parse().unwrap();
+        let merged = merge(&RenderSettings::default(), values).unwrap();
+        assert_eq!(merged.view, webcii::effects::overlay::View::FalseColor);
+    }
+}
@@ -0,0 +1,70 @@
+//! Folding glyphs down to 7-bit ASCII for `--ascii-only` output.
+
+/// The closest printable ASCII stand-in for `glyph`. Box drawing keeps its
+/// shape, shading blocks and braille keep their density, and anything else
+/// outside ASCII becomes `?`.
+pub fn fold(glyph: char) -> char {
+    match glyph {
+        ' '..='~' => glyph,
+        '\u{3000}' => ' ',
+        '─' | '━' | '—' | '–' => '-',
+        '│' | '┃' => '|',
+        '┼' | '╋' | '┌' | '┐' | '└' | '┘' => '+',
+        '╱' => '/',
+        '╲' => '\\',
+        '╳' => 'X',
+        '╭' | '╮' => '.',
+        '╯' | '╰' => '\'',
+        '█' => '@',
+        '▓' => '%',
+        '▒' => '+',
+        '░' => '.',
+        '▀' => '"',
+        '▄' => '_',
+        '▁' | '▂' => '_',
+        '▃' => '-',
+        '▅' | '▆' => '=',
+        '▇' => '#',
+        '▘' | '▝' | '▖' | '▗' => '.',
+        '▌' | '▐' => '|',
+        '▞' => '/',
+        '▚' => '\\',
+        '▛' | '▜' | '▙' | '▟' => '#',
+        '●' => 'O',
+        '•' => 'o',
+        '\u{2800}'..='\u{28FF}' => match (glyph as u32 - 0x2800).count_ones() {
+            0 => ' ',
+            1..=2 => '.',
+            3..=4 => ':',
+            5..=6 => '+',
+            _ => '#',
+        },
+        _ => '?',
+    }
+}
+
+/// [`fold`] applied to every character of `text`.
+pub fn fold_str(text: &str) -> String {
+    text.chars().map(fold).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::{ASCII_CHARS, charset::BLOCK_CHARS, color::HIGH_CONTRAST_CHARS};
+
+    #[test]
+    fn every_ramp_folds_to_printable_ascii() {
+        for glyph in ASCII_CHARS
+            .iter()
+            .chain(&BLOCK_CHARS)
+            .chain(&HIGH_CONTRAST_CHARS)
+        {
+            let folded = fold(*glyph);
+            assert!(folded.is_ascii() && !folded.is_ascii_control(), "{glyph}");
+        }
+        assert_eq!(fold('┼'), '+');
+        assert_eq!(fold('\u{28FF}'), '#');
+        assert_eq!(fold_str("webcii — cam0"), "webcii - cam0");
+    }
+}
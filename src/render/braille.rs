@@ -0,0 +1,98 @@
+//! Braille rasterizer: each cell shows a 2x4 grid of dots from the U+2800
+//! block, each dot thresholded on its own source pixel.
+
+use image::RgbImage;
+
+use super::{brightness, cell_origin};
+
+/// The empty braille pattern; dot bits are added to it.
+pub const BLANK: char = '\u{2800}';
+
+/// Bit for the dot in column `x`, row `y` of a cell, per the Unicode braille
+/// numbering (dots 1-3 and 7 down the left, 4-6 and 8 down the right).
+pub(crate) const DOT_BITS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+/// Brightness spread below which a cell counts as flat and is thresholded
+/// against mid-grey instead of its own mean, so noise in an even area
+/// doesn't come out as a scatter of dots.
+const MIN_CONTRAST: u8 = 24;
+const FLAT_THRESHOLD: u8 = 128;
+
+/// Color and glyph for cell (`tx`, `ty`) of a `cols` x `rows` grid over a
+/// `width` x `height` image. Dots light up where the pixel is brighter than
+/// the cell's mean; the color is the average of the lit pixels, or of all
+/// eight when none are lit.
+pub fn cell(
+    image: &RgbImage,
+    tx: usize,
+    ty: usize,
+    width: usize,
+    height: usize,
+    cols: usize,
+    rows: usize,
+) -> ([u8; 3], char) {
+    let mut samples = [([0u8; 3], 0u8); 8];
+    for (i, sample) in samples.iter_mut().enumerate() {
+        let (dx, dy) = (i % 2, i / 2);
+        let (x, y) = cell_origin(tx * 2 + dx, ty * 4 + dy, width, height, cols * 2, rows * 4);
+        let [r, g, b] = image.get_pixel(x as u32, y as u32).0;
+        *sample = ([r, g, b], brightness(r, g, b));
+    }
+
+    let (min, max) = samples
+        .iter()
+        .fold((u8::MAX, 0), |(min, max), &(_, v)| (min.min(v), max.max(v)));
+    let threshold = if max - min < MIN_CONTRAST {
+        FLAT_THRESHOLD
+    } else {
+        (samples.iter().map(|&(_, v)| v as u32).sum::<u32>() / 8) as u8
+    };
+
+    let mut bits = 0;
+    let mut sum = [0u32; 3];
+    let mut lit = 0;
+    for (i, &(color, value)) in samples.iter().enumerate() {
+        if value > threshold {
+            bits |= DOT_BITS[i / 2][i % 2];
+            for (sum, channel) in sum.iter_mut().zip(color) {
+                *sum += channel as u32;
+            }
+            lit += 1;
+        }
+    }
+    if lit == 0 {
+        for (color, _) in samples {
+            for (sum, channel) in sum.iter_mut().zip(color) {
+                *sum += channel as u32;
+            }
+        }
+        lit = 8;
+    }
+
+    let glyph = char::from_u32(BLANK as u32 + bits).expect("braille block is contiguous");
+    (sum.map(|s| (s / lit) as u8), glyph)
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgb;
+
+    use super::*;
+
+    #[test]
+    fn left_half_bright_lights_left_column() {
+        let image = RgbImage::from_fn(2, 4, |x, _| Rgb(if x == 0 { [200; 3] } else { [0; 3] }));
+        let (color, glyph) = cell(&image, 0, 0, 2, 4, 1, 1);
+        assert_eq!(glyph, '\u{2847}');
+        assert_eq!(color, [200; 3]);
+    }
+
+    #[test]
+    fn flat_cells_are_all_or_nothing() {
+        let dark = RgbImage::from_pixel(2, 4, Rgb([40; 3]));
+        assert_eq!(cell(&dark, 0, 0, 2, 4, 1, 1), ([40; 3], BLANK));
+
+        let bright = RgbImage::from_pixel(2, 4, Rgb([220; 3]));
+        assert_eq!(cell(&bright, 0, 0, 2, 4, 1, 1).1, '\u{28FF}');
+    }
+}
@@ -0,0 +1,67 @@
+use super::edges::EdgeCell;
+
+/// Base output of one cell from the previous frame, before overlays.
+#[derive(Clone, Copy)]
+pub struct CachedCell {
+    pub key: u32,
+    pub edge: EdgeCell,
+    pub color: [u8; 3],
+    pub glyph: char,
+}
+
+/// Cheap fingerprint of everything a cell's base output is derived from: the
+/// 3x3 neighbourhood Sobel reads around (`x`, `y`), the previous frame's
+/// pixel the temporal blend mixes in, and `ramp`, which of the ramps the
+/// glyph is picked from (with an adaptive charset, that depends on the
+/// whole cell, not just the neighbourhood).
+///
+/// Channel sums are quantized so sensor noise doesn't defeat the cache on
+/// an otherwise static scene; the drift this allows is below what the
+/// 4-bit-per-channel color output can show anyway.
+pub fn tile_key(
+    current: &[u8],
+    previous: Option<&[u8]>,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    ramp: u32,
+) -> u32 {
+    let mut sums = [0u32; 3];
+    for ny in y.saturating_sub(1)..=(y + 1).min(height - 1) {
+        for nx in x.saturating_sub(1)..=(x + 1).min(width - 1) {
+            let idx = (ny * width + nx) * 3;
+            for (sum, &value) in sums.iter_mut().zip(&current[idx..idx + 3]) {
+                *sum += value as u32;
+            }
+        }
+    }
+
+    let idx = (y * width + x) * 3;
+    let prev = previous
+        .and_then(|prev| prev.get(idx..idx + 3))
+        .unwrap_or(&[0; 3]);
+
+    // FNV-1a over the quantized values.
+    let quantized = sums
+        .iter()
+        .map(|sum| sum >> 5)
+        .chain(prev.iter().map(|&v| v as u32 >> 4))
+        .chain([ramp]);
+    quantized.fold(0x811c_9dc5, |hash, value| {
+        (hash ^ value).wrapping_mul(0x0100_0193)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ramp_is_part_of_the_key() {
+        let pixels = [128; 4 * 4 * 3];
+        let key = |ramp| tile_key(&pixels, Some(&pixels), 1, 1, 4, 4, ramp);
+        assert_eq!(key(0), key(0));
+        assert_ne!(key(0), key(1));
+    }
+}
@@ -0,0 +1,132 @@
+//! Fitting a fixed-size canvas (`--size`) into whatever terminal it is shown
+//! in: centered when the terminal is bigger, center-cropped when smaller.
+
+use super::charset::is_wide;
+
+/// Where a canvas lands in the terminal and which part of it shows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Placement {
+    /// Terminal cell of the first visible canvas cell, as (column, row).
+    pub origin: (u16, u16),
+    /// Canvas columns and rows cut off on the left and top.
+    pub skip: (usize, usize),
+    /// Canvas columns and rows that fit.
+    pub visible: (usize, usize),
+}
+
+/// Places a `canvas` of (columns, rows) in a `terminal` of the same.
+pub fn place(canvas: (usize, usize), terminal: (usize, usize)) -> Placement {
+    let axis = |canvas: usize, terminal: usize| {
+        if canvas <= terminal {
+            ((terminal - canvas) / 2, 0, canvas)
+        } else {
+            (0, (canvas - terminal) / 2, terminal)
+        }
+    };
+    let (x, skip_x, cols) = axis(canvas.0, terminal.0);
+    let (y, skip_y, rows) = axis(canvas.1, terminal.1);
+    Placement {
+        origin: (x as u16, y as u16),
+        skip: (skip_x, skip_y),
+        visible: (cols, rows),
+    }
+}
+
+/// The visible part of rendered `rows`.
+pub fn crop(rows: &[String], placement: &Placement) -> Vec<String> {
+    let (skip_x, skip_y) = placement.skip;
+    let (cols, visible_rows) = placement.visible;
+    rows.iter()
+        .skip(skip_y)
+        .take(visible_rows)
+        .map(|row| crop_row(row, skip_x, cols))
+        .collect()
+}
+
+/// Columns `skip..skip + take` of a rendered row. Escape sequences before
+/// and inside that range are kept so the first visible glyph still gets the
+/// color it was rendered with, and so are the ones closing the row, so a
+/// background reset at its end isn't lost with the columns cut off.
+pub fn crop_row(row: &str, skip: usize, take: usize) -> String {
+    let mut out = String::with_capacity(row.len());
+    // Escapes past the visible columns, kept only if no glyph follows.
+    let mut trailing = String::new();
+    let mut column = 0;
+    let mut chars = row.chars();
+    while let Some(c) = chars.next() {
+        if c == '\x1b' {
+            let sink = if column >= skip + take {
+                &mut trailing
+            } else {
+                &mut out
+            };
+            // CSI: ESC [ parameters, then a final byte in @..~.
+            sink.push(c);
+            for c in chars.by_ref() {
+                sink.push(c);
+                if c != '[' && ('@'..='~').contains(&c) {
+                    break;
+                }
+            }
+            continue;
+        }
+        if column >= skip + take {
+            trailing.clear();
+            continue;
+        }
+        let width = if is_wide(c) { 2 } else { 1 };
+        if column >= skip && column + width <= skip + take {
+            out.push(c);
+        } else if column + width > skip {
+            // Half of a wide glyph: blank the column that shows.
+            out.push(' ');
+        }
+        column += width;
+    }
+    out.push_str(&trailing);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn small_canvas_is_centered_and_large_one_cropped() {
+        assert_eq!(
+            place((80, 20), (100, 30)),
+            Placement {
+                origin: (10, 5),
+                skip: (0, 0),
+                visible: (80, 20)
+            }
+        );
+        assert_eq!(
+            place((160, 50), (100, 50)),
+            Placement {
+                origin: (0, 0),
+                skip: (30, 0),
+                visible: (100, 50)
+            }
+        );
+    }
+
+    #[test]
+    fn cropping_keeps_the_color_of_the_first_visible_glyph() {
+        let row = "\x1b[31mab\x1b[32mcd\x1b[33mef";
+        assert_eq!(crop_row(row, 1, 2), "\x1b[31mb\x1b[32mc");
+        assert_eq!(
+            crop_row("\x1b[41mab\x1b[42mc\x1b[49m", 0, 1),
+            "\x1b[41ma\x1b[49m"
+        );
+        assert_eq!(crop_row(row, 0, 6), row);
+        assert_eq!(crop_row("plain", 2, 10), "ain");
+    }
+
+    #[test]
+    fn wide_glyphs_cut_in_half_are_blanked() {
+        // Columns: 木 covers 0-1, a is 2, 木 covers 3-4.
+        assert_eq!(crop_row("木a木", 1, 3), " a ");
+        assert_eq!(crop_row("木a木", 0, 5), "木a木");
+    }
+}
@@ -0,0 +1,168 @@
+use super::brightness;
+use crate::error::CharsetError;
+
+/// Shading blocks, densest first like [`super::ASCII_CHARS`]. Flat regions
+/// read better as solid fills than as letters that suggest detail which
+/// isn't there.
+pub const BLOCK_CHARS: [char; 5] = ['█', '▓', '▒', '░', ' '];
+
+/// Brightness variance across a cell below which it counts as flat. Sensor
+/// noise alone stays well under this on a lit scene.
+pub const FLAT_VARIANCE: f32 = 60.0;
+
+/// At most this many samples per axis when measuring a cell, so large
+/// frames on small grids don't cost more than a detailed one.
+const MAX_SAMPLES: usize = 8;
+
+/// Brightness variance over the source pixels in `[x0, x1) x [y0, y1)` of a
+/// `width`-wide RGB frame, sampled on a grid of at most
+/// [`MAX_SAMPLES`]² points.
+pub fn cell_variance(
+    pixels: &[u8],
+    width: usize,
+    x0: usize,
+    y0: usize,
+    x1: usize,
+    y1: usize,
+) -> f32 {
+    let (x1, y1) = (x1.max(x0 + 1), y1.max(y0 + 1));
+    let step_x = (x1 - x0).div_ceil(MAX_SAMPLES);
+    let step_y = (y1 - y0).div_ceil(MAX_SAMPLES);
+
+    let (mut sum, mut sum_sq, mut count) = (0u32, 0u32, 0u32);
+    for y in (y0..y1).step_by(step_y) {
+        for x in (x0..x1).step_by(step_x) {
+            let idx = (y * width + x) * 3;
+            let Some(p) = pixels.get(idx..idx + 3) else {
+                continue;
+            };
+            let value = brightness(p[0], p[1], p[2]) as u32;
+            sum += value;
+            sum_sq += value * value;
+            count += 1;
+        }
+    }
+
+    if count == 0 {
+        return 0.0;
+    }
+    let mean = sum as f32 / count as f32;
+    sum_sq as f32 / count as f32 - mean * mean
+}
+
+/// Whether a cell with brightness `variance` is drawn with blocks.
+pub fn is_flat(variance: f32) -> bool {
+    variance < FLAT_VARIANCE
+}
+
+/// The ramp for a cell with brightness `variance`: blocks for flat cells,
+/// `detail` for everything else.
+pub fn ramp_for(variance: f32, detail: &[char]) -> &[char] {
+    if is_flat(variance) {
+        &BLOCK_CHARS
+    } else {
+        detail
+    }
+}
+
+/// Checks a ramp given as text, glyphs for the darkest pixels first, and
+/// splits it into glyphs. Every glyph has to take up cells of its own, so
+/// control characters and combining marks are refused; double-width glyphs
+/// are fine, the renderer gives them two cells.
+pub fn parse_ramp(text: &str) -> Result<Vec<char>, CharsetError> {
+    let ramp: Vec<char> = text.chars().collect();
+    for &glyph in &ramp {
+        if glyph.is_control() {
+            return Err(CharsetError::Control(glyph));
+        }
+        if is_zero_width(glyph) {
+            return Err(CharsetError::ZeroWidth(glyph));
+        }
+    }
+    match ramp.len() {
+        len @ 0..2 => return Err(CharsetError::TooShort(len)),
+        len @ 257.. => return Err(CharsetError::TooLong(len)),
+        _ => {}
+    }
+    Ok(ramp)
+}
+
+/// Combining marks, zero-width spaces and joiners, and variation selectors.
+fn is_zero_width(glyph: char) -> bool {
+    matches!(glyph,
+        '\u{0300}'..='\u{036F}'
+        | '\u{1AB0}'..='\u{1AFF}'
+        | '\u{1DC0}'..='\u{1DFF}'
+        | '\u{200B}'..='\u{200F}'
+        | '\u{2060}'..='\u{2064}'
+        | '\u{20D0}'..='\u{20FF}'
+        | '\u{FE00}'..='\u{FE0F}'
+        | '\u{FE20}'..='\u{FE2F}')
+}
+
+/// East Asian wide and fullwidth blocks, and emoji: the glyphs terminals
+/// give two columns.
+pub fn is_wide(glyph: char) -> bool {
+    matches!(glyph,
+        '\u{1100}'..='\u{115F}'
+        | '\u{2E80}'..='\u{303E}'
+        | '\u{3041}'..='\u{33FF}'
+        | '\u{3400}'..='\u{4DBF}'
+        | '\u{4E00}'..='\u{9FFF}'
+        | '\u{A000}'..='\u{A4CF}'
+        | '\u{AC00}'..='\u{D7A3}'
+        | '\u{F900}'..='\u{FAFF}'
+        | '\u{FE30}'..='\u{FE4F}'
+        | '\u{FF00}'..='\u{FF60}'
+        | '\u{FFE0}'..='\u{FFE6}'
+        | '\u{1F300}'..='\u{1F64F}'
+        | '\u{1F900}'..='\u{1F9FF}'
+        | '\u{20000}'..='\u{3FFFD}')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::ASCII_CHARS;
+
+    #[test]
+    fn flat_cell_gets_blocks_and_busy_cell_keeps_detail() {
+        // 4x1 RGB: uniform grey, then alternating black and white.
+        let flat = [100; 12];
+        let busy = [0, 0, 0, 255, 255, 255, 0, 0, 0, 255, 255, 255];
+
+        let flat_variance = cell_variance(&flat, 4, 0, 0, 4, 1);
+        let busy_variance = cell_variance(&busy, 4, 0, 0, 4, 1);
+        assert_eq!(flat_variance, 0.0);
+        assert!(busy_variance > 16_000.0);
+
+        assert_eq!(ramp_for(flat_variance, &ASCII_CHARS), BLOCK_CHARS);
+        assert_eq!(ramp_for(busy_variance, &ASCII_CHARS), ASCII_CHARS);
+    }
+
+    #[test]
+    fn ramps_take_any_printable_glyphs() {
+        assert_eq!(parse_ramp(" .:-=+*#%@").unwrap().len(), 10);
+        assert_eq!(parse_ramp(" ░▒▓█"), Ok(vec![' ', '░', '▒', '▓', '█']));
+        // Halfwidth katakana is narrow.
+        assert!(parse_ramp(" ｱｲｳ").is_ok());
+
+        assert_eq!(parse_ramp("#"), Err(CharsetError::TooShort(1)));
+        assert_eq!(
+            parse_ramp(&"#".repeat(300)),
+            Err(CharsetError::TooLong(300))
+        );
+        assert_eq!(parse_ramp(" .\t#"), Err(CharsetError::Control('\t')));
+        assert_eq!(
+            parse_ramp(" e\u{301}#"),
+            Err(CharsetError::ZeroWidth('\u{301}'))
+        );
+        assert_eq!(parse_ramp(" 木#").unwrap()[1], '木');
+    }
+
+    #[test]
+    fn empty_or_out_of_bounds_cell_is_flat() {
+        assert_eq!(cell_variance(&[], 4, 0, 0, 0, 0), 0.0);
+        assert_eq!(cell_variance(&[9; 3], 1, 3, 3, 3, 3), 0.0);
+    }
+}
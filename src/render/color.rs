@@ -0,0 +1,412 @@
+use std::{env, str::FromStr, sync::OnceLock};
+
+use serde::{Deserialize, Serialize};
+
+use crate::effects::overlay::luma;
+
+/// How a cell's RGB value is turned into an SGR escape.
+///
+/// Every colorizer maps a pixel to an index into its own escape table, so the
+/// row builder can skip re-emitting an escape while the index stays the same.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Colorizer {
+    /// 24-bit `38;2` escapes, quantized to 4 bits per channel.
+    TrueColor,
+    /// The xterm 256-color palette (`38;5`), for terminals and tmux setups
+    /// that don't pass 24-bit color through.
+    Ansi256,
+    /// The 8 basic ANSI colors, with bold standing in for the bright half,
+    /// matched perceptually.
+    Ansi8,
+    /// 24-bit greys by luma, for a black and white picture on a color
+    /// terminal.
+    Gray,
+    /// Bright white on black with a bold-only charset, for low vision and
+    /// monochrome displays.
+    HighContrast,
+    /// No color escapes at all, only the glyph ramp; selected when
+    /// `NO_COLOR` is set.
+    Monochrome,
+}
+
+/// Glyph ramp for [`Colorizer::HighContrast`], darkest first. Thin glyphs
+/// such as `.` or `'` are left out on purpose since they read as noise.
+pub const HIGH_CONTRAST_CHARS: [char; 5] = [' ', '+', '#', '@', '█'];
+
+/// Channel levels of the 6x6x6 cube in palette entries 16 to 231.
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+/// Palette entries 16 and up: the cube, then a 24-step grey ramp.
+const ANSI256_FIRST: usize = 16;
+const ANSI256_COLORS: usize = 240;
+
+impl Colorizer {
+    /// Picks a colorizer from `NO_COLOR`, `COLORTERM` and `TERM`, falling back
+    /// to truecolor.
+    pub fn detect() -> Self {
+        // https://no-color.org: any non-empty value disables color.
+        if env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty()) {
+            return Colorizer::Monochrome;
+        }
+
+        if matches!(
+            env::var("COLORTERM").as_deref(),
+            Ok("truecolor") | Ok("24bit")
+        ) {
+            return Colorizer::TrueColor;
+        }
+
+        match env::var("TERM").as_deref() {
+            Ok(term) if term.ends_with("256color") => Colorizer::Ansi256,
+            Ok("linux") | Ok("ansi") | Ok("cons25") => Colorizer::Ansi8,
+            Ok(term) if term.starts_with("vt") => Colorizer::Ansi8,
+            _ => Colorizer::TrueColor,
+        }
+    }
+
+    /// The mode the runtime color hotkey switches to.
+    pub fn next(self) -> Self {
+        match self {
+            Colorizer::TrueColor => Colorizer::Ansi256,
+            Colorizer::Ansi256 => Colorizer::Ansi8,
+            Colorizer::Ansi8 => Colorizer::Gray,
+            Colorizer::Gray => Colorizer::HighContrast,
+            Colorizer::HighContrast => Colorizer::Monochrome,
+            Colorizer::Monochrome => Colorizer::TrueColor,
+        }
+    }
+
+    /// Escape sequences indexed by [`Colorizer::index`].
+    pub fn palette(self) -> Vec<String> {
+        match self {
+            Colorizer::TrueColor => (0..4096)
+                .map(|i| {
+                    let r = ((i >> 8) & 0xF) * 17;
+                    let g = ((i >> 4) & 0xF) * 17;
+                    let b = (i & 0xF) * 17;
+                    format!("\x1b[38;2;{};{};{}m", r, g, b)
+                })
+                .collect(),
+            Colorizer::Ansi256 => (0..ANSI256_COLORS)
+                .map(|i| format!("\x1b[38;5;{}m", ANSI256_FIRST + i))
+                .collect(),
+            Colorizer::Ansi8 => (0..16)
+                .map(|i| {
+                    let weight = if i & 0b1000 != 0 { 1 } else { 22 };
+                    format!("\x1b[{};3{}m", weight, i & 0b111)
+                })
+                .collect(),
+            Colorizer::Gray => (0..256)
+                .map(|v| format!("\x1b[38;2;{};{};{}m", v, v, v))
+                .collect(),
+            Colorizer::HighContrast => vec!["\x1b[0;1;97;40m".to_string()],
+            Colorizer::Monochrome => vec![String::new()],
+        }
+    }
+
+    /// Background escapes indexed like [`Colorizer::palette`]; empty for
+    /// the colorizers that don't draw in color.
+    pub fn background_palette(self) -> Vec<String> {
+        match self {
+            Colorizer::TrueColor => (0..4096)
+                .map(|i| {
+                    let r = ((i >> 8) & 0xF) * 17;
+                    let g = ((i >> 4) & 0xF) * 17;
+                    let b = (i & 0xF) * 17;
+                    format!("\x1b[48;2;{};{};{}m", r, g, b)
+                })
+                .collect(),
+            Colorizer::Ansi256 => (0..ANSI256_COLORS)
+                .map(|i| format!("\x1b[48;5;{}m", ANSI256_FIRST + i))
+                .collect(),
+            Colorizer::Gray => (0..256)
+                .map(|v| format!("\x1b[48;2;{};{};{}m", v, v, v))
+                .collect(),
+            // Backgrounds have no bold, so both halves share the basic eight.
+            Colorizer::Ansi8 => (0..16).map(|i| format!("\x1b[4{}m", i & 0b111)).collect(),
+            Colorizer::HighContrast | Colorizer::Monochrome => Vec::new(),
+        }
+    }
+
+    /// Maps a color to the one its palette entry shows, for dithering; `None`
+    /// for the colorizers that don't draw in color.
+    pub fn quantizer(self) -> Option<impl Fn([u8; 3]) -> [u8; 3]> {
+        let color: fn(usize) -> [u8; 3] = match self {
+            Colorizer::TrueColor => {
+                |i| [(i >> 8) & 0xF, (i >> 4) & 0xF, i & 0xF].map(|c| c as u8 * 17)
+            }
+            Colorizer::Ansi256 => ansi256_rgb,
+            Colorizer::Ansi8 => ansi8_rgb,
+            Colorizer::Gray => |i| [i as u8; 3],
+            Colorizer::HighContrast | Colorizer::Monochrome => return None,
+        };
+        Some(move |[r, g, b]: [u8; 3]| color(self.index(r, g, b)))
+    }
+
+    pub fn index(self, r: u8, g: u8, b: u8) -> usize {
+        match self {
+            Colorizer::TrueColor => {
+                let r_idx = (r / 16) as usize;
+                let g_idx = (g / 16) as usize;
+                let b_idx = (b / 16) as usize;
+                (r_idx << 8) | (g_idx << 4) | b_idx
+            }
+            Colorizer::Ansi256 => ansi256_lookup()[lookup_key(r, g, b)] as usize,
+            Colorizer::Ansi8 => ansi8_index(r, g, b),
+            Colorizer::Gray => luma(r, g, b) as usize,
+            Colorizer::HighContrast | Colorizer::Monochrome => 0,
+        }
+    }
+}
+
+impl FromStr for Colorizer {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "truecolor" | "24bit" => Ok(Colorizer::TrueColor),
+            "256" | "ansi256" => Ok(Colorizer::Ansi256),
+            "16" | "ansi8" => Ok(Colorizer::Ansi8),
+            "gray" | "grey" => Ok(Colorizer::Gray),
+            "high-contrast" => Ok(Colorizer::HighContrast),
+            "none" | "monochrome" => Ok(Colorizer::Monochrome),
+            _ => Err(format!(
+                "unknown color mode `{}`; expected truecolor, 256, 16, gray, high-contrast or none",
+                s
+            )),
+        }
+    }
+}
+
+/// Index into the 5-bit-per-channel lookup table.
+fn lookup_key(r: u8, g: u8, b: u8) -> usize {
+    (r as usize >> 3) << 10 | (g as usize >> 3) << 5 | b as usize >> 3
+}
+
+/// Nearest 256-color entry, as an index from [`ANSI256_FIRST`], for every
+/// 5-bit-per-channel color; built on first use.
+fn ansi256_lookup() -> &'static [u8] {
+    static LOOKUP: OnceLock<Vec<u8>> = OnceLock::new();
+    LOOKUP.get_or_init(|| build_lookup(ansi256_nearest))
+}
+
+/// `nearest` for every key of [`lookup_key`].
+fn build_lookup(nearest: fn(u8, u8, u8) -> u8) -> Vec<u8> {
+    (0..1 << 15)
+        .map(|key: usize| {
+            // The middle of the 8 values each key covers.
+            let channel = |shift: usize| ((key >> shift & 0x1f) << 3 | 4) as u8;
+            nearest(channel(10), channel(5), channel(0))
+        })
+        .collect()
+}
+
+/// The color of xterm 256-color code `code` (`38;5;code`), past the 16
+/// basic colors terminals let users theme.
+pub fn xterm256_rgb(code: u8) -> Option<[u8; 3]> {
+    (code as usize).checked_sub(ANSI256_FIRST).map(ansi256_rgb)
+}
+
+/// The color of 256-color entry `ANSI256_FIRST + index`.
+fn ansi256_rgb(index: usize) -> [u8; 3] {
+    match index {
+        0..216 => [index / 36, index / 6 % 6, index % 6].map(|level| CUBE_LEVELS[level]),
+        _ => [8 + (index - 216) as u8 * 10; 3],
+    }
+}
+
+/// The cube color or grey closest to (`r`, `g`, `b`).
+fn ansi256_nearest(r: u8, g: u8, b: u8) -> u8 {
+    let distance = |[cr, cg, cb]: [u8; 3]| {
+        [(r, cr), (g, cg), (b, cb)]
+            .iter()
+            .map(|&(a, b)| (a as i32 - b as i32).pow(2))
+            .sum::<i32>()
+    };
+    let level = |c: u8| {
+        (0..CUBE_LEVELS.len())
+            .min_by_key(|&i| (CUBE_LEVELS[i] as i32 - c as i32).abs())
+            .unwrap_or(0)
+    };
+    let (ri, gi, bi) = (level(r), level(g), level(b));
+    let cube = [CUBE_LEVELS[ri], CUBE_LEVELS[gi], CUBE_LEVELS[bi]];
+
+    // Greys run 8, 18, ..., 238.
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let grey_step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let grey = 8 + grey_step * 10;
+
+    if distance([grey; 3]) < distance(cube) {
+        216 + grey_step
+    } else {
+        (ri * 36 + gi * 6 + bi) as u8
+    }
+}
+
+// Low bits are the ANSI color number (bit 0 red, bit 1 green, bit 2 blue),
+// bit 3 selects bold.
+fn ansi8_index(r: u8, g: u8, b: u8) -> usize {
+    ansi8_lookup()[lookup_key(r, g, b)] as usize
+}
+
+/// Nearest of the 16 colors for every 5-bit-per-channel color; built on
+/// first use.
+fn ansi8_lookup() -> &'static [u8] {
+    static LOOKUP: OnceLock<Vec<u8>> = OnceLock::new();
+    LOOKUP.get_or_init(|| build_lookup(ansi8_nearest))
+}
+
+/// The basic color, bold or not, closest to (`r`, `g`, `b`) in CIELAB, so
+/// that closeness is what the eye sees: in plain RGB distance warm skin
+/// tones land nearer magenta than red or yellow.
+fn ansi8_nearest(r: u8, g: u8, b: u8) -> u8 {
+    static TARGETS: OnceLock<[[f32; 3]; 16]> = OnceLock::new();
+    let targets = TARGETS.get_or_init(|| {
+        std::array::from_fn(|i| {
+            let [r, g, b] = ansi8_rgb(i);
+            lab(r, g, b)
+        })
+    });
+    let color = lab(r, g, b);
+    let distance = |target: &[f32; 3]| {
+        target
+            .iter()
+            .zip(color)
+            .map(|(t, c)| (t - c).powi(2))
+            .sum::<f32>()
+    };
+    (0..targets.len())
+        .min_by(|&a, &b| distance(&targets[a]).total_cmp(&distance(&targets[b])))
+        .unwrap_or(0) as u8
+}
+
+/// The VGA color for an [`ansi8_index`]: 170 for a dim channel, 255 plus 85
+/// for a bright one, and bold black as dark grey.
+fn ansi8_rgb(index: usize) -> [u8; 3] {
+    let (on, base) = if index & 0b1000 != 0 {
+        (255, 85)
+    } else {
+        (170, 0)
+    };
+    [0b001, 0b010, 0b100].map(|bit| if index & bit != 0 { on } else { base })
+}
+
+/// sRGB to CIELAB under D65.
+fn lab(r: u8, g: u8, b: u8) -> [f32; 3] {
+    let linear = |c: u8| {
+        let c = c as f32 / 255.0;
+        if c <= 0.04045 {
+            c / 12.92
+        } else {
+            ((c + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    let (r, g, b) = (linear(r), linear(g), linear(b));
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+    let f = |t: f32| {
+        if t > 0.008856 {
+            t.cbrt()
+        } else {
+            7.787 * t + 16.0 / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn indices_stay_within_palette() {
+        let mut colorizer = Colorizer::TrueColor;
+        loop {
+            let palette = colorizer.palette();
+            for [r, g, b] in [[0, 0, 0], [255, 255, 255], [255, 0, 0], [12, 200, 90]] {
+                assert!(colorizer.index(r, g, b) < palette.len());
+            }
+            colorizer = colorizer.next();
+            if colorizer == Colorizer::TrueColor {
+                break;
+            }
+        }
+    }
+
+    #[test]
+    fn truecolor_quantizes_to_nibbles() {
+        let palette = Colorizer::TrueColor.palette();
+        let idx = Colorizer::TrueColor.index(255, 128, 0);
+        assert_eq!(idx, 0xF80);
+        assert_eq!(palette[idx], "\x1b[38;2;255;136;0m");
+    }
+
+    #[test]
+    fn background_palette_matches_foreground_indices() {
+        for colorizer in [
+            Colorizer::TrueColor,
+            Colorizer::Ansi256,
+            Colorizer::Ansi8,
+            Colorizer::Gray,
+        ] {
+            assert_eq!(
+                colorizer.background_palette().len(),
+                colorizer.palette().len()
+            );
+        }
+        assert_eq!(
+            Colorizer::TrueColor.background_palette()[0xF80],
+            "\x1b[48;2;255;136;0m"
+        );
+        assert!(Colorizer::Monochrome.background_palette().is_empty());
+    }
+
+    #[test]
+    fn quantizers_land_on_palette_colors() {
+        let truecolor = Colorizer::TrueColor.quantizer().unwrap();
+        assert_eq!(truecolor([255, 140, 3]), [255, 136, 0]);
+        let ansi256 = Colorizer::Ansi256.quantizer().unwrap();
+        assert_eq!(ansi256([95, 135, 175]), [95, 135, 175]);
+        assert_eq!(ansi256([118; 3]), [118; 3]);
+        let ansi8 = Colorizer::Ansi8.quantizer().unwrap();
+        assert_eq!(ansi8([170, 0, 0]), [170, 0, 0]);
+        assert_eq!(ansi8([85; 3]), [85; 3]);
+        assert!(Colorizer::Monochrome.quantizer().is_none());
+    }
+
+    #[test]
+    fn ansi256_picks_cube_colors_and_greys() {
+        let palette = Colorizer::Ansi256.palette();
+        let escape = |r, g, b| &palette[Colorizer::Ansi256.index(r, g, b)];
+        assert_eq!(escape(0, 0, 0), "\x1b[38;5;16m");
+        assert_eq!(escape(255, 0, 0), "\x1b[38;5;196m");
+        assert_eq!(escape(255, 255, 255), "\x1b[38;5;231m");
+        // This grey sits between cube levels, so the ramp wins.
+        assert_eq!(escape(118, 118, 118), "\x1b[38;5;243m");
+        // Palette entry 67.
+        assert_eq!(ansi256_nearest(95, 135, 175), 51);
+        assert_eq!("256".parse(), Ok(Colorizer::Ansi256));
+    }
+
+    #[test]
+    fn gray_and_none_leave_out_hue() {
+        let gray = Colorizer::Gray;
+        assert_eq!(gray.palette()[gray.index(255, 0, 0)], "\x1b[38;2;76;76;76m");
+        assert_eq!("none".parse(), Ok(Colorizer::Monochrome));
+        assert!(Colorizer::Monochrome.palette().iter().all(String::is_empty));
+    }
+
+    #[test]
+    fn ansi8_maps_to_the_perceptually_nearest_color() {
+        assert_eq!(ansi8_index(255, 0, 0), 1);
+        assert_eq!(ansi8_index(0, 120, 0), 2);
+        assert_eq!(ansi8_index(200, 40, 200), 5);
+        assert_eq!(ansi8_index(20, 22, 18), 0);
+        assert_eq!(ansi8_index(230, 230, 225), 7 | 0b1000);
+        // Skin tones stay brown rather than turning magenta.
+        assert_eq!(ansi8_index(224, 172, 105), 3);
+        assert_eq!(ansi8_index(198, 134, 66), 3);
+    }
+}
@@ -0,0 +1,95 @@
+//! Live render settings: a [`watch`] channel whose sender is the control
+//! handle, for the key handler or a program embedding webcii, and whose
+//! receiving end the render loop checks between frames.
+
+use tokio::sync::watch;
+
+use super::settings::RenderSettings;
+
+/// Changes the settings the next frame renders with, from anywhere: every
+/// change replaces the settings as a whole, so the render loop never sees
+/// half of one.
+pub type Control = watch::Sender<RenderSettings>;
+
+/// What a settings change means for whatever consumes the rendered rows.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Change {
+    /// Cells look different, but the rows are made the same way.
+    Look,
+    /// The mode or colorizer changed, so rows are built from other glyphs
+    /// and escapes: a sink holding state about the old ones, say an encoder
+    /// or the terminal's current attributes, has to start over.
+    Restart,
+}
+
+impl Change {
+    fn between(old: &RenderSettings, new: &RenderSettings) -> Self {
+        if old.mode != new.mode || old.colorizer != new.colorizer {
+            Change::Restart
+        } else {
+            Change::Look
+        }
+    }
+}
+
+/// A control handle and the render loop's end of it, starting from
+/// `initial`.
+pub fn channel(initial: RenderSettings) -> (Control, LiveSettings) {
+    let (control, receiver) = watch::channel(initial.clone());
+    let live = LiveSettings {
+        receiver,
+        current: initial,
+    };
+    (control, live)
+}
+
+/// The render loop's copy of the settings. It is only refreshed when the
+/// channel's version moves on, so an unchanged frame reads it without
+/// touching the channel's lock.
+pub struct LiveSettings {
+    receiver: watch::Receiver<RenderSettings>,
+    current: RenderSettings,
+}
+
+impl LiveSettings {
+    pub fn current(&self) -> &RenderSettings {
+        &self.current
+    }
+
+    /// Picks up the latest settings if they changed since the last call,
+    /// and says what kind of change it was.
+    pub fn update(&mut self) -> Option<Change> {
+        // A closed channel just means nobody changes the settings anymore.
+        if !self.receiver.has_changed().unwrap_or(false) {
+            return None;
+        }
+        let new = self.receiver.borrow_and_update().clone();
+        let change = Change::between(&self.current, &new);
+        self.current = new;
+        Some(change)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::settings::Mode;
+
+    #[test]
+    fn changes_are_picked_up_once_and_classified() {
+        let (control, mut live) = channel(RenderSettings::default());
+        assert_eq!(live.update(), None);
+
+        control.send_modify(|s| s.zebra = true);
+        assert_eq!(live.update(), Some(Change::Look));
+        assert!(live.current().zebra);
+        assert_eq!(live.update(), None);
+
+        control.send_modify(|s| s.mode = Mode::Braille);
+        assert_eq!(live.update(), Some(Change::Restart));
+
+        drop(control);
+        assert_eq!(live.update(), None);
+        assert_eq!(live.current().mode, Mode::Braille);
+    }
+}
@@ -0,0 +1,53 @@
+//! Error diffusion over the cell grid: the difference between each cell's
+//! color and the palette color it ends up as is spread onto the cells not
+//! yet visited, so areas average out to their true color instead of
+//! snapping to one palette entry and banding.
+
+/// Floyd–Steinberg: replaces every color of the `cols`-wide grid `colors`
+/// with its `quantize`d one, left to right and top to bottom, passing 7/16
+/// of the error right, and 3/16, 5/16 and 1/16 to the row below.
+pub fn floyd_steinberg(colors: &mut [[u8; 3]], cols: usize, quantize: impl Fn([u8; 3]) -> [u8; 3]) {
+    // Error carried into this row and the next, one slot of padding on
+    // either side so the edges need no special cases.
+    let mut current = vec![[0.0f32; 3]; cols + 2];
+    let mut next = vec![[0.0f32; 3]; cols + 2];
+
+    for row in colors.chunks_mut(cols) {
+        for (tx, color) in row.iter_mut().enumerate() {
+            let carried = current[tx + 1];
+            let wanted: [f32; 3] =
+                std::array::from_fn(|c| (color[c] as f32 + carried[c]).clamp(0.0, 255.0));
+            let shown = quantize(wanted.map(|c| c.round() as u8));
+            for c in 0..3 {
+                let error = wanted[c] - shown[c] as f32;
+                current[tx + 2][c] += error * 7.0 / 16.0;
+                next[tx][c] += error * 3.0 / 16.0;
+                next[tx + 1][c] += error * 5.0 / 16.0;
+                next[tx + 2][c] += error / 16.0;
+            }
+            *color = shown;
+        }
+        std::mem::swap(&mut current, &mut next);
+        next.fill([0.0; 3]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn flat_area_averages_to_its_color() {
+        // Black and white only: a 25% grey comes out as one white cell in
+        // four.
+        let mut colors = vec![[64; 3]; 64];
+        floyd_steinberg(
+            &mut colors,
+            8,
+            |c| if c[0] < 128 { [0; 3] } else { [255; 3] },
+        );
+        let white = colors.iter().filter(|c| c[0] == 255).count();
+        assert!((14..=18).contains(&white), "{} white cells", white);
+        assert!(colors.iter().all(|c| c[0] == 0 || c[0] == 255));
+    }
+}
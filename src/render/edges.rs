@@ -0,0 +1,343 @@
+/// Gradient magnitude an edge needs before it replaces the ramp glyph.
+pub const EDGE_THRESHOLD: f32 = 30.0;
+/// Gradient magnitude above which edges switch to heavy box-drawing glyphs.
+pub const HEAVY_EDGE_THRESHOLD: f32 = 120.0;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum SobelEdge {
+    None,
+    Horizontal,
+    Vertical,
+    DiagonalUp,
+    DiagonalDown,
+}
+
+/// Edge detected for one terminal cell.
+#[derive(Clone, Copy)]
+pub struct EdgeCell {
+    pub edge: SobelEdge,
+    pub heavy: bool,
+    /// Where a diagonal edge crosses the cell: -1 above its center, cutting
+    /// off a top corner, 1 below it, 0 through it.
+    pub shift: i8,
+}
+
+impl EdgeCell {
+    pub const NONE: Self = Self {
+        edge: SobelEdge::None,
+        heavy: false,
+        shift: 0,
+    };
+}
+
+pub fn sobel_gradient(
+    decoded: &image::ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+) -> Option<(i32, i32)> {
+    if x == 0 || y == 0 || x >= width - 1 || y >= height - 1 {
+        return None;
+    }
+
+    let get_brightness = |px: u32, py: u32| -> i32 {
+        let pixel = decoded.get_pixel(px, py);
+        ((pixel[0] as u32 + pixel[1] as u32 + pixel[2] as u32) / 3) as i32
+    };
+
+    // 3x3 neighborhood
+    let nw = get_brightness((x - 1) as u32, (y - 1) as u32);
+    let n = get_brightness((x) as u32, (y - 1) as u32);
+    let ne = get_brightness((x + 1) as u32, (y - 1) as u32);
+    let w = get_brightness((x - 1) as u32, (y) as u32);
+    let e = get_brightness((x + 1) as u32, (y) as u32);
+    let sw = get_brightness((x - 1) as u32, (y + 1) as u32);
+    let s = get_brightness((x) as u32, (y + 1) as u32);
+    let se = get_brightness((x + 1) as u32, (y + 1) as u32);
+
+    // Sobel operator kernels
+    // Gx (horizontal gradient):     Gy (vertical gradient):
+    //   -1  0  +1                      -1  -2  -1
+    //   -2  0  +2                       0   0   0
+    //   -1  0  +1                      +1  +2  +1
+
+    let gx = -nw + ne - 2 * w + 2 * e - sw + se;
+    let gy = -nw - 2 * n - ne + sw + 2 * s + se;
+
+    Some((gx, gy))
+}
+
+pub fn sobel_magnitude(gx: i32, gy: i32) -> f32 {
+    ((gx * gx + gy * gy) as f32).sqrt()
+}
+
+pub fn sobel_detect_edge(
+    decoded: &image::ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+    threshold: f32,
+) -> EdgeCell {
+    let Some((gx, gy)) = sobel_gradient(decoded, x, y, width, height) else {
+        return EdgeCell::NONE;
+    };
+
+    let magnitude = sobel_magnitude(gx, gy);
+
+    if magnitude <= threshold {
+        return EdgeCell::NONE;
+    }
+
+    let angle = (gy as f32).atan2(gx as f32);
+
+    let degrees = angle.to_degrees();
+    let normalized = if degrees < 0.0 {
+        degrees + 360.0
+    } else {
+        degrees
+    };
+
+    let edge = match normalized {
+        a if !(22.5..337.5).contains(&a) => SobelEdge::Vertical,
+        a if (22.5..67.5).contains(&a) => SobelEdge::DiagonalDown,
+        a if (67.5..112.5).contains(&a) => SobelEdge::Horizontal,
+        a if (112.5..157.5).contains(&a) => SobelEdge::DiagonalUp,
+        a if (157.5..202.5).contains(&a) => SobelEdge::Vertical,
+        a if (202.5..247.5).contains(&a) => SobelEdge::DiagonalDown,
+        a if (247.5..292.5).contains(&a) => SobelEdge::Horizontal,
+        _ => SobelEdge::DiagonalUp,
+    };
+
+    EdgeCell {
+        edge,
+        heavy: magnitude > HEAVY_EDGE_THRESHOLD,
+        shift: 0,
+    }
+}
+
+/// Finds where a diagonal `cell` edge crosses the source pixels from
+/// `origin` up to `end`, by where the brightness at the cell's center falls
+/// between the two corners on either side of the edge: close to the lower
+/// corner's means the edge passes above the center, and the other way
+/// round.
+pub fn locate_diagonal(
+    decoded: &image::ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    cell: EdgeCell,
+    origin: (usize, usize),
+    end: (usize, usize),
+    width: usize,
+    height: usize,
+) -> EdgeCell {
+    let ((x0, y0), (x1, y1)) = (origin, end);
+    // Brightness a given number of quarters into the cell.
+    let sample = |qx: usize, qy: usize| -> i32 {
+        let x = (x0 + (x1.saturating_sub(x0)) * qx / 4).min(width - 1);
+        let y = (y0 + (y1.saturating_sub(y0)) * qy / 4).min(height - 1);
+        let pixel = decoded.get_pixel(x as u32, y as u32);
+        pixel[0] as i32 + pixel[1] as i32 + pixel[2] as i32
+    };
+    let (upper, lower) = match cell.edge {
+        SobelEdge::DiagonalUp => (sample(1, 1), sample(3, 3)),
+        SobelEdge::DiagonalDown => (sample(3, 1), sample(1, 3)),
+        _ => return cell,
+    };
+    if upper == lower {
+        return cell;
+    }
+    let toward_lower = (sample(2, 2) - upper) as f32 / (lower - upper) as f32;
+    let shift = if toward_lower > 2.0 / 3.0 {
+        -1
+    } else if toward_lower < 1.0 / 3.0 {
+        1
+    } else {
+        0
+    };
+    EdgeCell { shift, ..cell }
+}
+
+/// Color for an edge glyph at pixel (`x`, `y`): the brightest pixel of its
+/// 3x3 neighborhood, i.e. the lit side of the boundary. The pixel itself
+/// often sits on the dark side, and an outline drawn in it all but vanishes.
+pub fn edge_color(
+    decoded: &image::ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    x: usize,
+    y: usize,
+    width: usize,
+    height: usize,
+) -> [u8; 3] {
+    let mut brightest = decoded.get_pixel(x as u32, y as u32).0;
+    let brightness = |[r, g, b]: [u8; 3]| r as u32 + g as u32 + b as u32;
+    let mut best = brightness(brightest);
+    for py in y.saturating_sub(1)..(y + 2).min(height) {
+        for px in x.saturating_sub(1)..(x + 2).min(width) {
+            let pixel = decoded.get_pixel(px as u32, py as u32).0;
+            let value = brightness(pixel);
+            if value > best {
+                (brightest, best) = (pixel, value);
+            }
+        }
+    }
+    brightest
+}
+
+/// Picks the glyph for the edge at (`tx`, `ty`) in a `cols`-wide grid, or
+/// `None` when the cell has no edge.
+///
+/// Strong edges use the heavy box-drawing variants. Where a horizontal and a
+/// vertical edge touch, the cell becomes a cross so outlines close up
+/// instead of leaving a gap at the corner. Diagonals work the same way with
+/// `╳`, and one that only cuts off a corner of its cell, see
+/// [`locate_diagonal`], becomes the arc around that corner.
+pub fn edge_glyph(edges: &[EdgeCell], tx: usize, ty: usize, cols: usize) -> Option<char> {
+    let cell = edges[ty * cols + tx];
+    let rows = edges.len() / cols;
+    let edge_at = |x: usize, y: usize| edges[y * cols + x].edge;
+    // Whether the cell `dx`, `dy` away has a `edge` edge.
+    let neighbor_is = |dx: isize, dy: isize, edge: SobelEdge| match (
+        tx.checked_add_signed(dx),
+        ty.checked_add_signed(dy),
+    ) {
+        (Some(x), Some(y)) if x < cols && y < rows => edge_at(x, y) == edge,
+        _ => false,
+    };
+
+    let crossed = match cell.edge {
+        SobelEdge::None => return None,
+        SobelEdge::DiagonalUp => {
+            let crossed = neighbor_is(-1, -1, SobelEdge::DiagonalDown)
+                || neighbor_is(1, 1, SobelEdge::DiagonalDown);
+            return Some(match (crossed, cell.shift) {
+                (true, _) => '╳',
+                (false, -1) => '╯',
+                (false, 1) => '╭',
+                _ => '╱',
+            });
+        }
+        SobelEdge::DiagonalDown => {
+            let crossed = neighbor_is(1, -1, SobelEdge::DiagonalUp)
+                || neighbor_is(-1, 1, SobelEdge::DiagonalUp);
+            return Some(match (crossed, cell.shift) {
+                (true, _) => '╳',
+                (false, -1) => '╰',
+                (false, 1) => '╮',
+                _ => '╲',
+            });
+        }
+        SobelEdge::Horizontal => {
+            (ty > 0 && edge_at(tx, ty - 1) == SobelEdge::Vertical)
+                || (ty + 1 < rows && edge_at(tx, ty + 1) == SobelEdge::Vertical)
+        }
+        SobelEdge::Vertical => {
+            (tx > 0 && edge_at(tx - 1, ty) == SobelEdge::Horizontal)
+                || (tx + 1 < cols && edge_at(tx + 1, ty) == SobelEdge::Horizontal)
+        }
+    };
+
+    let glyph = match (cell.edge, crossed, cell.heavy) {
+        (_, true, false) => '┼',
+        (_, true, true) => '╋',
+        (SobelEdge::Horizontal, false, false) => '─',
+        (SobelEdge::Horizontal, false, true) => '━',
+        (_, false, false) => '│',
+        (_, false, true) => '┃',
+    };
+    Some(glyph)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{ImageBuffer, Rgb};
+
+    fn fixture(f: impl Fn(u32, u32) -> u8) -> ImageBuffer<Rgb<u8>, Vec<u8>> {
+        ImageBuffer::from_fn(8, 8, |x, y| Rgb([f(x, y); 3]))
+    }
+
+    #[test]
+    fn flat_image_has_no_edges() {
+        let image = fixture(|_, _| 128);
+        let cell = sobel_detect_edge(&image, 4, 4, 8, 8, EDGE_THRESHOLD);
+        assert!(cell.edge == SobelEdge::None);
+    }
+
+    #[test]
+    fn step_edges_are_oriented() {
+        let left_right = fixture(|x, _| if x < 4 { 0 } else { 255 });
+        let cell = sobel_detect_edge(&left_right, 4, 4, 8, 8, EDGE_THRESHOLD);
+        assert!(cell.edge == SobelEdge::Vertical);
+        assert!(cell.heavy);
+
+        let top_bottom = fixture(|_, y| if y < 4 { 0 } else { 20 });
+        let cell = sobel_detect_edge(&top_bottom, 4, 4, 8, 8, EDGE_THRESHOLD);
+        assert!(cell.edge == SobelEdge::Horizontal);
+        assert!(!cell.heavy);
+    }
+
+    #[test]
+    fn border_pixels_are_skipped() {
+        let image = fixture(|x, _| if x < 4 { 0 } else { 255 });
+        assert!(sobel_gradient(&image, 0, 4, 8, 8).is_none());
+        assert!(sobel_gradient(&image, 4, 7, 8, 8).is_none());
+    }
+
+    #[test]
+    fn edges_take_the_lit_side() {
+        let image = fixture(|x, _| if x < 4 { 10 } else { 200 });
+        assert_eq!(edge_color(&image, 3, 4, 8, 8), [200; 3]);
+        assert_eq!(edge_color(&image, 0, 0, 8, 8), [10; 3]);
+    }
+
+    #[test]
+    fn touching_edges_form_junctions() {
+        let h = EdgeCell {
+            edge: SobelEdge::Horizontal,
+            ..EdgeCell::NONE
+        };
+        let v = EdgeCell {
+            edge: SobelEdge::Vertical,
+            ..EdgeCell::NONE
+        };
+        let n = EdgeCell::NONE;
+
+        // . v .
+        // h h .
+        let edges = [n, v, n, h, h, n];
+        assert_eq!(edge_glyph(&edges, 0, 1, 3), Some('─'));
+        assert_eq!(edge_glyph(&edges, 1, 1, 3), Some('┼'));
+        assert_eq!(edge_glyph(&edges, 1, 0, 3), Some('│'));
+        assert_eq!(edge_glyph(&edges, 2, 1, 3), None);
+    }
+
+    #[test]
+    fn diagonals_follow_where_they_cross_the_cell() {
+        let up = EdgeCell {
+            edge: SobelEdge::DiagonalUp,
+            ..EdgeCell::NONE
+        };
+        let locate = |image: &ImageBuffer<Rgb<u8>, Vec<u8>>| {
+            locate_diagonal(image, up, (0, 0), (8, 8), 8, 8).shift
+        };
+        // Dark above the line x + y = c, bright below it.
+        let step = |c: u32| fixture(move |x, y| if x + y < c { 0 } else { 255 });
+        assert_eq!(locate(&step(6)), -1);
+        assert_eq!(locate(&step(10)), 1);
+        assert_eq!(locate(&fixture(|x, y| ((x + y) * 16) as u8)), 0);
+
+        let shifted = |shift| EdgeCell { shift, ..up };
+        assert_eq!(edge_glyph(&[up], 0, 0, 1), Some('╱'));
+        assert_eq!(edge_glyph(&[shifted(-1)], 0, 0, 1), Some('╯'));
+        assert_eq!(edge_glyph(&[shifted(1)], 0, 0, 1), Some('╭'));
+
+        // ╱ .
+        // . ╲   crossing diagonals meet in a ╳
+        let down = EdgeCell {
+            edge: SobelEdge::DiagonalDown,
+            ..EdgeCell::NONE
+        };
+        let edges = [up, EdgeCell::NONE, EdgeCell::NONE, down];
+        assert_eq!(edge_glyph(&edges, 0, 0, 2), Some('╳'));
+        assert_eq!(edge_glyph(&edges, 1, 1, 2), Some('╲'));
+    }
+}
@@ -0,0 +1,67 @@
+//! Half-block rasterizer: each cell is an upper half block (`▀`) whose
+//! foreground is the top half of the cell and whose background is the
+//! bottom half, doubling the vertical resolution.
+
+use image::RgbImage;
+
+use super::{brightness, cell_origin};
+
+pub const UPPER_HALF: char = '▀';
+const LOWER_HALF: char = '▄';
+const FULL: char = '█';
+
+/// Brightness above which a half counts as lit when there are no colors to
+/// draw it with.
+const LIT_THRESHOLD: u8 = 128;
+
+/// Top and bottom colors for cell (`tx`, `ty`) of a `cols` x `rows` grid
+/// over a `width` x `height` image.
+pub fn cell(
+    image: &RgbImage,
+    tx: usize,
+    ty: usize,
+    width: usize,
+    height: usize,
+    cols: usize,
+    rows: usize,
+) -> ([u8; 3], [u8; 3]) {
+    let half = |dy| {
+        let (x, y) = cell_origin(tx, ty * 2 + dy, width, height, cols, rows * 2);
+        image.get_pixel(x as u32, y as u32).0
+    };
+    (half(0), half(1))
+}
+
+/// The glyph standing in for a `top` and `bottom` pair on a colorizer
+/// without backgrounds: each half is either lit or left blank.
+pub fn glyph(top: [u8; 3], bottom: [u8; 3]) -> char {
+    let lit = |[r, g, b]: [u8; 3]| brightness(r, g, b) > LIT_THRESHOLD;
+    match (lit(top), lit(bottom)) {
+        (false, false) => ' ',
+        (true, false) => UPPER_HALF,
+        (false, true) => LOWER_HALF,
+        (true, true) => FULL,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgb;
+
+    use super::*;
+
+    #[test]
+    fn halves_sample_their_own_rows() {
+        let image = RgbImage::from_fn(1, 4, |_, y| Rgb([y as u8 * 60; 3]));
+        assert_eq!(cell(&image, 0, 0, 1, 4, 1, 2), ([0; 3], [60; 3]));
+        assert_eq!(cell(&image, 0, 1, 1, 4, 1, 2), ([120; 3], [180; 3]));
+    }
+
+    #[test]
+    fn colorless_glyph_follows_the_lit_halves() {
+        assert_eq!(glyph([255; 3], [0; 3]), UPPER_HALF);
+        assert_eq!(glyph([0; 3], [255; 3]), LOWER_HALF);
+        assert_eq!(glyph([255; 3], [255; 3]), FULL);
+        assert_eq!(glyph([0; 3], [0; 3]), ' ');
+    }
+}
@@ -0,0 +1,116 @@
+use super::ramp_index;
+
+/// How far (in brightness levels) a cell has to move past the edges of its
+/// current ramp bucket before the glyph switches right away.
+const MARGIN: i32 = 6;
+/// How many consecutive frames a smaller change has to persist before the
+/// glyph follows it anyway.
+const SETTLE_FRAMES: u8 = 3;
+
+const UNSET: u16 = u16::MAX;
+
+/// Per-cell memory of the ramp index that was last drawn, so sensor noise
+/// around a bucket boundary doesn't make the glyph flicker between neighbors.
+#[derive(Clone, Copy)]
+pub struct CellHysteresis {
+    index: u16,
+    /// Length of the ramp `index` points into.
+    levels: u16,
+    pending: u16,
+    frames: u8,
+}
+
+impl Default for CellHysteresis {
+    fn default() -> Self {
+        Self {
+            index: UNSET,
+            levels: 0,
+            pending: UNSET,
+            frames: 0,
+        }
+    }
+}
+
+impl CellHysteresis {
+    /// Returns the ramp index to draw for `brightness` on a ramp with
+    /// `levels` entries, updating the cell's state. A cell that moves onto
+    /// a ramp of a different length starts over.
+    pub fn settle(&mut self, brightness: u8, levels: usize) -> usize {
+        let candidate = ramp_index(brightness, levels);
+        let current = self.index as usize;
+
+        if self.index == UNSET || self.levels as usize != levels || candidate == current {
+            self.levels = levels as u16;
+            return self.switch(candidate);
+        }
+
+        // Brightness range that maps onto the current index: [lo, hi).
+        let lo = (current * 256).div_ceil(levels) as i32;
+        let hi = ((current + 1) * 256).div_ceil(levels) as i32;
+        let brightness = brightness as i32;
+        if brightness < lo - MARGIN || brightness >= hi + MARGIN {
+            return self.switch(candidate);
+        }
+
+        if self.pending as usize == candidate {
+            self.frames = self.frames.saturating_add(1);
+        } else {
+            self.pending = candidate as u16;
+            self.frames = 1;
+        }
+
+        if self.frames >= SETTLE_FRAMES {
+            self.switch(candidate)
+        } else {
+            current
+        }
+    }
+
+    fn switch(&mut self, index: usize) -> usize {
+        self.index = index as u16;
+        self.pending = UNSET;
+        self.frames = 0;
+        index
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const LEVELS: usize = 8; // 32 brightness levels per bucket
+
+    #[test]
+    fn small_changes_wait_for_settle_frames() {
+        let mut cell = CellHysteresis::default();
+        assert_eq!(cell.settle(60, LEVELS), 1);
+
+        // Just across the boundary into bucket 2.
+        assert_eq!(cell.settle(66, LEVELS), 1);
+        assert_eq!(cell.settle(66, LEVELS), 1);
+        assert_eq!(cell.settle(66, LEVELS), 2);
+    }
+
+    #[test]
+    fn noise_around_boundary_does_not_flicker() {
+        let mut cell = CellHysteresis::default();
+        cell.settle(62, LEVELS);
+        for brightness in [65, 62, 66, 63, 64, 61] {
+            assert_eq!(cell.settle(brightness, LEVELS), 1);
+        }
+    }
+
+    #[test]
+    fn large_changes_switch_immediately() {
+        let mut cell = CellHysteresis::default();
+        cell.settle(60, LEVELS);
+        assert_eq!(cell.settle(200, LEVELS), 6);
+    }
+
+    #[test]
+    fn ramp_change_resets_state() {
+        let mut cell = CellHysteresis::default();
+        cell.settle(250, 70);
+        assert_eq!(cell.settle(250, 5), 4);
+    }
+}
@@ -0,0 +1,313 @@
+//! Frame to glyph grid: downscale, charmap, colorize and serialize.
+
+pub mod ascii;
+pub mod braille;
+pub mod cache;
+pub mod canvas;
+pub mod charset;
+pub mod color;
+pub mod control;
+pub mod dither;
+pub mod edges;
+pub mod halfblock;
+pub mod hysteresis;
+pub mod pixel;
+pub mod preset;
+pub mod quadrant;
+pub mod raster;
+mod renderer;
+pub mod settings;
+pub mod tone;
+
+use std::io::Write;
+
+use crossterm::{cursor, queue};
+
+use self::hysteresis::CellHysteresis;
+pub use self::renderer::Renderer;
+use crate::error::SinkError;
+
+/// Default glyph ramp, densest first.
+pub const ASCII_CHARS: [char; 70] = [
+    '$', '@', 'B', '%', '8', '&', 'W', 'M', '#', '*', 'o', 'a', 'h', 'k', 'b', 'd', 'p', 'q', 'w',
+    'm', 'Z', 'O', '0', 'Q', 'L', 'C', 'J', 'U', 'Y', 'X', 'z', 'c', 'v', 'u', 'n', 'x', 'r', 'j',
+    'f', 't', '/', '\\', '|', '(', ')', '1', '{', '}', '[', ']', '?', '-', '_', '+', '~', '<', '>',
+    'i', '!', 'l', 'I', ';', ':', ',', '"', '^', '`', '\'', '.', ' ',
+];
+
+/// Downscale: the source pixel sampled for cell (`tx`, `ty`) when a
+/// `width` x `height` frame is mapped onto `cols` x `rows` cells.
+pub fn cell_origin(
+    tx: usize,
+    ty: usize,
+    width: usize,
+    height: usize,
+    cols: usize,
+    rows: usize,
+) -> (usize, usize) {
+    (tx * width / cols, ty * height / rows)
+}
+
+/// The pixel at (`x`, `y`) of a previous RGB frame, if it has one there.
+pub fn previous_pixel(previous: &[u8], x: usize, y: usize, width: usize) -> Option<[u8; 3]> {
+    let idx = (y * width + x) * 3;
+    previous.get(idx..idx + 3).map(|p| [p[0], p[1], p[2]])
+}
+
+/// Temporal blend: 70% of the current pixel, 30% of the previous one.
+pub fn blend(current: [u8; 3], previous: [u8; 3]) -> [u8; 3] {
+    [0, 1, 2].map(|i| ((current[i] as u16 * 7 + previous[i] as u16 * 3) / 10) as u8)
+}
+
+pub fn brightness(r: u8, g: u8, b: u8) -> u8 {
+    ((r as u32 + g as u32 + b as u32) / 3) as u8
+}
+
+/// Charmap: index into a ramp of `levels` glyphs for `brightness`.
+pub fn ramp_index(brightness: u8, levels: usize) -> usize {
+    brightness as usize * levels / 256
+}
+
+/// Blend and charmap for one cell: the color to draw and the ramp glyph,
+/// with the glyph filtered through the cell's hysteresis state.
+pub fn shade(
+    pixel: [u8; 3],
+    previous: Option<[u8; 3]>,
+    state: &mut CellHysteresis,
+    ramp: &[char],
+) -> ([u8; 3], char) {
+    let color = match previous {
+        Some(previous) => blend(pixel, previous),
+        None => pixel,
+    };
+    let [r, g, b] = color;
+    (color, ramp[state.settle(brightness(r, g, b), ramp.len())])
+}
+
+/// Puts the terminal's own background back after a cell that set one.
+const DEFAULT_BACKGROUND: &str = "\x1b[49m";
+
+/// Serialize: builds one row of output, only emitting a color escape when
+/// the palette index differs from the previous cell's. Backgrounds are
+/// tracked the same way, separately from the foreground.
+pub struct RowWriter<'a> {
+    palette: &'a [String],
+    backgrounds: &'a [String],
+    buffer: String,
+    last_color_idx: usize,
+    last_background_idx: usize,
+}
+
+impl<'a> RowWriter<'a> {
+    pub fn new(palette: &'a [String], cols: usize) -> Self {
+        Self {
+            palette,
+            backgrounds: &[],
+            buffer: String::with_capacity(cols * 20),
+            last_color_idx: usize::MAX,
+            last_background_idx: usize::MAX,
+        }
+    }
+
+    /// Background escapes for [`RowWriter::push_pair`], indexed like the
+    /// palette.
+    pub fn with_backgrounds(mut self, backgrounds: &'a [String]) -> Self {
+        self.backgrounds = backgrounds;
+        self
+    }
+
+    /// A glyph on the terminal's default background.
+    pub fn push(&mut self, glyph: char, color_idx: usize) {
+        self.reset_background();
+        self.push_color(color_idx);
+        self.buffer.push(glyph);
+    }
+
+    /// A glyph with both its foreground and background colored.
+    pub fn push_pair(&mut self, glyph: char, color_idx: usize, background_idx: usize) {
+        self.push_color(color_idx);
+        if background_idx != self.last_background_idx {
+            self.buffer.push_str(&self.backgrounds[background_idx]);
+            self.last_background_idx = background_idx;
+        }
+        self.buffer.push(glyph);
+    }
+
+    /// A space on a colored background. Its foreground doesn't show, so
+    /// no foreground escape is written and a run of cells in one color
+    /// costs a single escape.
+    pub fn push_blank(&mut self, background_idx: usize) {
+        if background_idx != self.last_background_idx {
+            self.buffer.push_str(&self.backgrounds[background_idx]);
+            self.last_background_idx = background_idx;
+        }
+        self.buffer.push(' ');
+    }
+
+    fn push_color(&mut self, color_idx: usize) {
+        if color_idx != self.last_color_idx {
+            self.buffer.push_str(&self.palette[color_idx]);
+            self.last_color_idx = color_idx;
+        }
+    }
+
+    fn reset_background(&mut self) {
+        if self.last_background_idx != usize::MAX {
+            self.buffer.push_str(DEFAULT_BACKGROUND);
+            self.last_background_idx = usize::MAX;
+        }
+    }
+
+    /// The row, with the background reset so it doesn't bleed past the end.
+    pub fn finish(mut self) -> String {
+        self.reset_background();
+        self.buffer
+    }
+}
+
+/// Diff: indices of rows that differ from (or are missing in) `previous`.
+pub fn changed_rows<'a>(
+    rows: &'a [String],
+    previous: &'a [String],
+) -> impl Iterator<Item = usize> + 'a {
+    rows.iter()
+        .enumerate()
+        .filter(|(i, row)| previous.get(*i) != Some(*row))
+        .map(|(i, _)| i)
+}
+
+/// Writes a frame with its top-left corner at `origin` (column, row): only
+/// the changed rows when the previous frame is known, otherwise everything.
+pub fn write_frame<W: Write>(
+    out: &mut W,
+    rows: &[String],
+    previous: Option<&[String]>,
+    origin: (u16, u16),
+) -> Result<(), SinkError> {
+    let (x, y) = origin;
+    if let Some(previous) = previous {
+        for row_idx in changed_rows(rows, previous) {
+            queue!(out, cursor::MoveTo(x, y + row_idx as u16))?;
+            write!(out, "{}", rows[row_idx])?;
+        }
+    } else {
+        queue!(out, cursor::MoveTo(x, y))?;
+
+        for (i, row) in rows.iter().enumerate() {
+            write!(out, "{}", row)?;
+
+            if i + 1 < rows.len() {
+                if x == 0 {
+                    write!(out, "\r\n")?;
+                } else {
+                    queue!(out, cursor::MoveTo(x, y + i as u16 + 1))?;
+                }
+            }
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::color::Colorizer;
+    use super::*;
+
+    #[test]
+    fn cell_origin_scales_onto_grid() {
+        assert_eq!(cell_origin(0, 0, 640, 480, 80, 24), (0, 0));
+        assert_eq!(cell_origin(40, 12, 640, 480, 80, 24), (320, 240));
+        assert_eq!(cell_origin(79, 23, 640, 480, 80, 24), (632, 460));
+    }
+
+    #[test]
+    fn previous_pixel_is_bounds_checked() {
+        let frame = [1, 2, 3, 4, 5, 6];
+        assert_eq!(previous_pixel(&frame, 1, 0, 2), Some([4, 5, 6]));
+        assert_eq!(previous_pixel(&frame, 0, 1, 2), None);
+    }
+
+    #[test]
+    fn blend_weights_current_frame() {
+        assert_eq!(blend([100, 0, 255], [0, 100, 255]), [70, 30, 255]);
+    }
+
+    #[test]
+    fn ramp_index_covers_full_range() {
+        assert_eq!(ramp_index(0, ASCII_CHARS.len()), 0);
+        assert_eq!(ramp_index(255, ASCII_CHARS.len()), ASCII_CHARS.len() - 1);
+        assert_eq!(ramp_index(128, 2), 1);
+    }
+
+    #[test]
+    fn row_writer_emits_escape_only_on_color_change() {
+        let palette = vec!["<a>".to_string(), "<b>".to_string()];
+        let mut row = RowWriter::new(&palette, 4);
+        for (glyph, idx) in [('x', 0), ('y', 0), ('z', 1), ('w', 0)] {
+            row.push(glyph, idx);
+        }
+        assert_eq!(row.finish(), "<a>xy<b>z<a>w");
+    }
+
+    #[test]
+    fn row_writer_tracks_backgrounds_separately() {
+        let palette = vec!["<a>".to_string(), "<b>".to_string()];
+        let backgrounds = vec!["[a]".to_string(), "[b]".to_string()];
+        let mut row = RowWriter::new(&palette, 4).with_backgrounds(&backgrounds);
+        row.push_pair('x', 0, 1);
+        row.push_pair('y', 0, 0);
+        row.push_pair('z', 1, 0);
+        row.push('w', 1);
+        row.push_pair('v', 1, 0);
+        assert_eq!(row.finish(), "<a>[b]x[a]y<b>z\x1b[49mw[a]v\x1b[49m");
+    }
+
+    #[test]
+    fn changed_rows_includes_new_rows() {
+        let previous = vec!["a".to_string(), "b".to_string()];
+        let rows = vec!["a".to_string(), "c".to_string(), "d".to_string()];
+        assert_eq!(changed_rows(&rows, &previous).collect::<Vec<_>>(), [1, 2]);
+    }
+
+    #[test]
+    fn write_frame_full_and_diff() {
+        let rows = vec!["ab".to_string(), "cd".to_string()];
+
+        let mut full = Vec::new();
+        write_frame(&mut full, &rows, None, (0, 0)).unwrap();
+        assert_eq!(full, b"\x1b[1;1Hab\r\ncd");
+
+        let previous = vec!["ab".to_string(), "xx".to_string()];
+        let mut diff = Vec::new();
+        write_frame(&mut diff, &rows, Some(&previous), (0, 0)).unwrap();
+        assert_eq!(diff, b"\x1b[2;1Hcd");
+
+        let mut offset = Vec::new();
+        write_frame(&mut offset, &rows, None, (3, 1)).unwrap();
+        assert_eq!(offset, b"\x1b[2;4Hab\x1b[3;4Hcd");
+    }
+
+    // Golden fixture: a horizontal grey ramp through downscale, charmap,
+    // colorize and serialize.
+    #[test]
+    fn grey_ramp_golden() {
+        let width = 8;
+        let frame: Vec<[u8; 3]> = (0..width).map(|x| [(x * 255 / 7) as u8; 3]).collect();
+        let palette = Colorizer::TrueColor.palette();
+        let mut states = [CellHysteresis::default(); 4];
+
+        let mut row = RowWriter::new(&palette, 4);
+        for (tx, state) in states.iter_mut().enumerate() {
+            let (x, _) = cell_origin(tx, 0, width, 1, 4, 1);
+            let (color, glyph) = shade(frame[x], None, state, &ASCII_CHARS);
+            let [r, g, b] = color;
+            row.push(glyph, Colorizer::TrueColor.index(r, g, b));
+        }
+
+        assert_eq!(
+            row.finish(),
+            "\x1b[38;2;0;0;0m$\x1b[38;2;68;68;68mm\x1b[38;2;153;153;153mt\x1b[38;2;221;221;221ml"
+        );
+    }
+}
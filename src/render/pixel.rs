@@ -0,0 +1,45 @@
+//! Pixel-grid rasterizer: each cell is a space on a background in the
+//! cell's color, so the terminal becomes a plain grid of pixels.
+
+use image::RgbImage;
+
+use super::{brightness, charset::BLOCK_CHARS, quadrant};
+
+/// The color for cell (`tx`, `ty`) of a `cols` x `rows` grid over a
+/// `width` x `height` image: the average of its four quadrants, so fine
+/// detail blends instead of flickering between neighbors.
+pub fn cell(
+    image: &RgbImage,
+    tx: usize,
+    ty: usize,
+    width: usize,
+    height: usize,
+    cols: usize,
+    rows: usize,
+) -> [u8; 3] {
+    let samples = quadrant::samples(image, tx, ty, width, height, cols, rows);
+    let channel = |c: usize| (samples.iter().map(|s| s[c] as u32).sum::<u32>() / 4) as u8;
+    [channel(0), channel(1), channel(2)]
+}
+
+/// The shading block standing in for `color` on a colorizer without
+/// backgrounds: solid for the brightest cells, blank for the darkest.
+pub fn glyph([r, g, b]: [u8; 3]) -> char {
+    let darkness = 255 - brightness(r, g, b) as usize;
+    BLOCK_CHARS[darkness * BLOCK_CHARS.len() / 256]
+}
+
+#[cfg(test)]
+mod tests {
+    use image::Rgb;
+
+    use super::*;
+
+    #[test]
+    fn cells_average_their_quadrants() {
+        let image = RgbImage::from_fn(2, 2, |x, y| Rgb([(x + 2 * y) as u8 * 40; 3]));
+        assert_eq!(cell(&image, 0, 0, 2, 2, 1, 1), [60; 3]);
+        assert_eq!(glyph([255; 3]), '█');
+        assert_eq!(glyph([0; 3]), ' ');
+    }
+}
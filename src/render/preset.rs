@@ -0,0 +1,74 @@
+//! Built-in glyph ramps, picked by name with `--charset-preset` and cycled
+//! through at runtime.
+
+/// A named ramp, densest glyph first like [`super::ASCII_CHARS`].
+pub struct Preset {
+    pub name: &'static str,
+    pub ramp: &'static str,
+}
+
+pub const PRESETS: [Preset; 5] = [
+    Preset {
+        name: "blocks",
+        ramp: "█▓▒░ ",
+    },
+    Preset {
+        name: "minimal",
+        ramp: "@#+:. ",
+    },
+    Preset {
+        name: "dense",
+        ramp: "█▓▒░@%#*+=-:. ",
+    },
+    // Fullwidth, so each glyph takes two cells.
+    Preset {
+        name: "katakana",
+        ramp: "ボヰネホメヌムヨサシラツニノヽ・\u{3000}",
+    },
+    Preset {
+        name: "dots",
+        ramp: "⣿⣷⣶⣦⣤⣄⣀⡀ ",
+    },
+];
+
+/// The preset called `name`.
+pub fn find(name: &str) -> Option<&'static Preset> {
+    PRESETS.iter().find(|preset| preset.name == name)
+}
+
+/// The ramp the cycle key switches to from `current`: the first preset from
+/// the built-in ramp or a custom one, the next one from a preset, and back
+/// to the built-in ramp (`None`) after the last.
+pub fn next(current: Option<&str>) -> Option<&'static str> {
+    let position = current.and_then(|ramp| PRESETS.iter().position(|p| p.ramp == ramp));
+    match position {
+        None => Some(PRESETS[0].ramp),
+        Some(i) => PRESETS.get(i + 1).map(|preset| preset.ramp),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::charset::parse_ramp;
+
+    #[test]
+    fn presets_are_valid_ramps() {
+        for preset in &PRESETS {
+            assert!(parse_ramp(preset.ramp).is_ok(), "{}", preset.name);
+        }
+        assert_eq!(find("dots").unwrap().ramp, PRESETS[4].ramp);
+        assert!(find("emoji").is_none());
+    }
+
+    #[test]
+    fn cycle_returns_to_the_built_in_ramp() {
+        let mut ramp = None;
+        for preset in &PRESETS {
+            ramp = next(ramp);
+            assert_eq!(ramp, Some(preset.ramp));
+        }
+        assert_eq!(next(ramp), None);
+        assert_eq!(next(Some(" .:#")), Some(PRESETS[0].ramp));
+    }
+}
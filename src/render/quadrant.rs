@@ -0,0 +1,135 @@
+//! Quadrant rasterizer: each cell shows 2x2 pixels as one of the sixteen
+//! quadrant block glyphs, split between a foreground and a background
+//! color.
+
+use image::RgbImage;
+
+use super::{brightness, cell_origin};
+
+/// Glyphs by the quadrants they fill: bit 0 top left, bit 1 top right,
+/// bit 2 bottom left, bit 3 bottom right.
+pub const GLYPHS: [char; 16] = [
+    ' ', '▘', '▝', '▀', '▖', '▌', '▞', '▛', '▗', '▚', '▐', '▜', '▄', '▙', '▟', '█',
+];
+
+/// Brightness above which a quadrant counts as lit when there are no colors
+/// to draw it with.
+const LIT_THRESHOLD: u8 = 128;
+
+/// The four pixels of cell (`tx`, `ty`) of a `cols` x `rows` grid over a
+/// `width` x `height` image, in bit order.
+pub fn samples(
+    image: &RgbImage,
+    tx: usize,
+    ty: usize,
+    width: usize,
+    height: usize,
+    cols: usize,
+    rows: usize,
+) -> [[u8; 3]; 4] {
+    std::array::from_fn(|i| {
+        let (dx, dy) = (i % 2, i / 2);
+        let (x, y) = cell_origin(tx * 2 + dx, ty * 2 + dy, width, height, cols * 2, rows * 2);
+        image.get_pixel(x as u32, y as u32).0
+    })
+}
+
+/// The glyph, foreground and background that reproduce `samples` best: of
+/// every way to split the four pixels into two clusters, the one whose
+/// cluster means leave the least squared error.
+pub fn split(samples: [[u8; 3]; 4]) -> (char, [u8; 3], [u8; 3]) {
+    let mut best = (u32::MAX, 0, [0; 3], [0; 3]);
+    // A pattern and its complement are the same split, so only the ones
+    // with the top-left quadrant in the foreground are tried. The full
+    // block goes first so flat cells don't pick a needless pattern.
+    for bits in (1..16).step_by(2).rev() {
+        let foreground = mean(&samples, |i| bits & (1 << i) != 0).unwrap_or_default();
+        let background = mean(&samples, |i| bits & (1 << i) == 0).unwrap_or(foreground);
+        let error: u32 = samples
+            .iter()
+            .enumerate()
+            .map(|(i, &pixel)| {
+                let center = if bits & (1 << i) != 0 {
+                    foreground
+                } else {
+                    background
+                };
+                distance(pixel, center)
+            })
+            .sum();
+        if error < best.0 {
+            best = (error, bits, foreground, background);
+        }
+    }
+    let (_, bits, foreground, background) = best;
+    (GLYPHS[bits], foreground, background)
+}
+
+/// The color and glyph for `samples` on a colorizer without backgrounds:
+/// lit quadrants are filled in the average of their colors.
+pub fn threshold(samples: [[u8; 3]; 4]) -> ([u8; 3], char) {
+    let lit = |i: usize| {
+        let [r, g, b] = samples[i];
+        brightness(r, g, b) > LIT_THRESHOLD
+    };
+    let bits = (0..4).filter(|&i| lit(i)).fold(0, |bits, i| bits | 1 << i);
+    let color = mean(&samples, lit)
+        .or_else(|| mean(&samples, |_| true))
+        .unwrap_or_default();
+    (color, GLYPHS[bits])
+}
+
+/// Mean color of the samples `included`, if there are any.
+fn mean(samples: &[[u8; 3]; 4], included: impl Fn(usize) -> bool) -> Option<[u8; 3]> {
+    let mut sum = [0u32; 3];
+    let mut count = 0;
+    for (i, pixel) in samples.iter().enumerate() {
+        if included(i) {
+            for (sum, &channel) in sum.iter_mut().zip(pixel) {
+                *sum += channel as u32;
+            }
+            count += 1;
+        }
+    }
+    (count > 0).then(|| sum.map(|s| (s / count) as u8))
+}
+
+fn distance(a: [u8; 3], b: [u8; 3]) -> u32 {
+    a.iter()
+        .zip(b)
+        .map(|(&a, b)| (a as i32 - b as i32).pow(2) as u32)
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const RED: [u8; 3] = [255, 0, 0];
+    const BLUE: [u8; 3] = [0, 0, 255];
+
+    #[test]
+    fn split_finds_the_two_color_pattern() {
+        // Red on the left column, blue on the right.
+        assert_eq!(split([RED, BLUE, RED, BLUE]), ('▌', RED, BLUE));
+        // Red only in the bottom right: the complement pattern, swapped.
+        assert_eq!(split([BLUE, BLUE, BLUE, RED]), ('▛', BLUE, RED));
+        assert_eq!(split([RED; 4]), ('█', RED, RED));
+    }
+
+    #[test]
+    fn threshold_fills_the_bright_quadrants() {
+        let white = [255; 3];
+        assert_eq!(threshold([white, [0; 3], [0; 3], white]), (white, '▚'));
+        assert_eq!(threshold([[10; 3]; 4]), ([10; 3], ' '));
+    }
+
+    #[test]
+    fn samples_cover_the_cell_in_bit_order() {
+        let image = RgbImage::from_fn(2, 2, |x, y| image::Rgb([(y * 2 + x) as u8; 3]));
+        assert_eq!(
+            samples(&image, 0, 0, 2, 2, 1, 1),
+            [[0; 3], [1; 3], [2; 3], [3; 3]]
+        );
+    }
+}
@@ -0,0 +1,248 @@
+//! Turning rendered rows back into pixels, for exporting frames as images:
+//! each cell is drawn with an 8x8 bitmap font stretched to terminal
+//! proportions, in the colors its escapes selected.
+
+use font8x8::{BASIC_FONTS, BLOCK_FONTS, BOX_FONTS, UnicodeFonts};
+use image::{Rgb, RgbImage};
+
+use super::{ascii, braille, charset::is_wide, color::xterm256_rgb};
+
+pub const CELL_WIDTH: u32 = 8;
+/// Font rows are doubled, since terminal cells are about twice as tall as
+/// they are wide.
+pub const CELL_HEIGHT: u32 = 16;
+
+const DEFAULT_FOREGROUND: [u8; 3] = [229, 229, 229];
+const DEFAULT_BACKGROUND: [u8; 3] = [0, 0, 0];
+
+/// xterm's colors for SGR 30-37, then the bright 90-97.
+const ANSI_COLORS: [[u8; 3]; 16] = [
+    [0, 0, 0],
+    [205, 0, 0],
+    [0, 205, 0],
+    [205, 205, 0],
+    [0, 0, 238],
+    [205, 0, 205],
+    [0, 205, 205],
+    [229, 229, 229],
+    [127, 127, 127],
+    [255, 0, 0],
+    [0, 255, 0],
+    [255, 255, 0],
+    [92, 92, 255],
+    [255, 0, 255],
+    [0, 255, 255],
+    [255, 255, 255],
+];
+
+/// A glyph with the foreground and background it was drawn in.
+type Cell = (char, [u8; 3], [u8; 3]);
+
+/// SGR state while walking a row.
+struct Pen {
+    foreground: [u8; 3],
+    background: [u8; 3],
+    /// Basic color index, kept so bold can brighten it.
+    basic: Option<usize>,
+    bold: bool,
+}
+
+impl Pen {
+    fn new() -> Self {
+        Self {
+            foreground: DEFAULT_FOREGROUND,
+            background: DEFAULT_BACKGROUND,
+            basic: None,
+            bold: false,
+        }
+    }
+
+    /// Applies the parameters of one `ESC [ ... m` sequence.
+    fn apply(&mut self, params: &str) {
+        let mut codes = params
+            .split(';')
+            .map(|code| code.parse::<u16>().unwrap_or(0));
+        while let Some(code) = codes.next() {
+            match code {
+                0 => *self = Pen::new(),
+                1 => self.bold = true,
+                22 => self.bold = false,
+                30..=37 => self.basic = Some(code as usize - 30),
+                90..=97 => self.basic = Some(code as usize - 90 + 8),
+                39 => {
+                    self.basic = None;
+                    self.foreground = DEFAULT_FOREGROUND;
+                }
+                40..=47 => self.background = ANSI_COLORS[code as usize - 40],
+                49 => self.background = DEFAULT_BACKGROUND,
+                38 | 48 => {
+                    // `2;r;g;b` from truecolor, `5;n` from the 256-color
+                    // colorizer; skip other forms.
+                    let rgb = match codes.next() {
+                        Some(2) => [(); 3].map(|_| codes.next().unwrap_or(0).min(255) as u8),
+                        Some(5) => {
+                            let code = codes.next().unwrap_or(0).min(255) as u8;
+                            xterm256_rgb(code).unwrap_or_else(|| ANSI_COLORS[code as usize])
+                        }
+                        _ => continue,
+                    };
+                    if code == 38 {
+                        self.basic = None;
+                        self.foreground = rgb;
+                    } else {
+                        self.background = rgb;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn foreground(&self) -> [u8; 3] {
+        match self.basic {
+            Some(index) if self.bold && index < 8 => ANSI_COLORS[index + 8],
+            Some(index) => ANSI_COLORS[index],
+            None => self.foreground,
+        }
+    }
+}
+
+/// Draws `rows` as they would appear on a terminal with a black background.
+pub fn rasterize(rows: &[String]) -> RgbImage {
+    let cells: Vec<Vec<Cell>> = rows.iter().map(|row| parse_row(row)).collect();
+    let cols = cells.iter().map(Vec::len).max().unwrap_or(0) as u32;
+    let mut image = RgbImage::from_pixel(
+        (cols * CELL_WIDTH).max(1),
+        (cells.len() as u32 * CELL_HEIGHT).max(1),
+        Rgb(DEFAULT_BACKGROUND),
+    );
+
+    for (ty, row) in cells.iter().enumerate() {
+        for (tx, &(glyph, foreground, background)) in row.iter().enumerate() {
+            let bitmap = bitmap(glyph);
+            for py in 0..CELL_HEIGHT {
+                let bits = bitmap[(py / 2) as usize];
+                for px in 0..CELL_WIDTH {
+                    let color = if bits & (1 << px) != 0 {
+                        foreground
+                    } else {
+                        background
+                    };
+                    image.put_pixel(
+                        tx as u32 * CELL_WIDTH + px,
+                        ty as u32 * CELL_HEIGHT + py,
+                        Rgb(color),
+                    );
+                }
+            }
+        }
+    }
+    image
+}
+
+/// The cells of a rendered row.
+fn parse_row(row: &str) -> Vec<Cell> {
+    let mut pen = Pen::new();
+    let mut cells = Vec::new();
+    let mut chars = row.chars();
+    while let Some(c) = chars.next() {
+        if c != '\x1b' {
+            cells.push((c, pen.foreground(), pen.background));
+            // The second column of a wide glyph.
+            if is_wide(c) {
+                cells.push((' ', pen.foreground(), pen.background));
+            }
+            continue;
+        }
+        if chars.next() != Some('[') {
+            continue;
+        }
+        let mut params = String::new();
+        for c in chars.by_ref() {
+            if ('@'..='~').contains(&c) {
+                if c == 'm' {
+                    pen.apply(&params);
+                }
+                break;
+            }
+            params.push(c);
+        }
+    }
+    cells
+}
+
+/// 8x8 bitmap for `glyph`, one byte per row with bit 0 on the left.
+fn bitmap(glyph: char) -> [u8; 8] {
+    if let Some(bitmap) = BASIC_FONTS
+        .get(glyph)
+        .or_else(|| BOX_FONTS.get(glyph))
+        .or_else(|| BLOCK_FONTS.get(glyph))
+    {
+        return bitmap;
+    }
+    if ('\u{2800}'..='\u{28FF}').contains(&glyph) {
+        return braille_bitmap(glyph as u32 - braille::BLANK as u32);
+    }
+    BASIC_FONTS.get(ascii::fold(glyph)).unwrap_or([0; 8])
+}
+
+/// Braille dots as 2x1 pixel blocks, two pixels apart down each column.
+fn braille_bitmap(bits: u32) -> [u8; 8] {
+    let mut bitmap = [0; 8];
+    for (row, row_bits) in braille::DOT_BITS.iter().enumerate() {
+        for (col, &bit) in row_bits.iter().enumerate() {
+            if bits & bit != 0 {
+                bitmap[row * 2] |= 0b11 << (col * 4 + 1);
+            }
+        }
+    }
+    bitmap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn escapes_set_cell_colors() {
+        assert_eq!(
+            parse_row("\x1b[38;2;10;20;30ma\x1b[1;31mb\x1b[0;1;97;40mc\x1b[0md"),
+            [
+                ('a', [10, 20, 30], DEFAULT_BACKGROUND),
+                ('b', ANSI_COLORS[9], DEFAULT_BACKGROUND),
+                ('c', ANSI_COLORS[15], ANSI_COLORS[0]),
+                ('d', DEFAULT_FOREGROUND, DEFAULT_BACKGROUND),
+            ]
+        );
+    }
+
+    #[test]
+    fn palette_escapes_use_the_xterm_colors() {
+        assert_eq!(
+            parse_row("\x1b[38;5;196ma\x1b[38;5;34mb\x1b[48;5;41mc\x1b[38;5;9;48;5;244md"),
+            [
+                ('a', [255, 0, 0], DEFAULT_BACKGROUND),
+                ('b', [0, 175, 0], DEFAULT_BACKGROUND),
+                ('c', [0, 175, 0], [0, 215, 95]),
+                ('d', ANSI_COLORS[9], [128; 3]),
+            ]
+        );
+    }
+
+    #[test]
+    fn full_block_fills_its_cell() {
+        let image = rasterize(&["\x1b[38;2;255;0;0m█ ".to_string()]);
+        assert_eq!(image.dimensions(), (2 * CELL_WIDTH, CELL_HEIGHT));
+        assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0]);
+        assert_eq!(image.get_pixel(7, 15).0, [255, 0, 0]);
+        assert_eq!(image.get_pixel(8, 0).0, DEFAULT_BACKGROUND);
+    }
+
+    #[test]
+    fn braille_dots_land_in_their_columns() {
+        // Dot 1 (top left) and dot 8 (bottom right).
+        let bitmap = braille_bitmap(0x01 | 0x80);
+        assert_eq!(bitmap[0], 0b0000_0110);
+        assert_eq!(bitmap[6], 0b0110_0000);
+    }
+}
@@ -0,0 +1,750 @@
+use rayon::{
+    iter::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator},
+    slice::{ParallelSlice, ParallelSliceMut},
+};
+
+use super::{
+    ASCII_CHARS, RowWriter, ascii, braille,
+    cache::{CachedCell, tile_key},
+    cell_origin,
+    charset::{cell_variance, is_flat, is_wide, parse_ramp, ramp_for},
+    color::{Colorizer, HIGH_CONTRAST_CHARS},
+    dither,
+    edges::{
+        EdgeCell, edge_color, edge_glyph, locate_diagonal, sobel_detect_edge, sobel_gradient,
+        sobel_magnitude,
+    },
+    halfblock,
+    hysteresis::CellHysteresis,
+    pixel, previous_pixel, quadrant,
+    settings::{Dither, EdgeColor, Mode, RenderSettings},
+    shade,
+};
+use crate::{
+    effects::{
+        blobs,
+        heatmap::Heatmap,
+        overlay::{self, Layer, View},
+        tracking::Trail,
+    },
+    error::RenderError,
+    source::Frame,
+};
+
+/// A cell as the video alone draws it, before overlays.
+#[derive(Clone, Copy, Default)]
+struct VideoCell {
+    glyph: char,
+    color: [u8; 3],
+    background: Option<[u8; 3]>,
+}
+
+/// Turns frames into rows of colored glyphs, keeping the per-cell state
+/// (hysteresis, cache, motion history) that carries over between frames.
+pub struct Renderer {
+    sobel_threshold: f32,
+    colorizer: Colorizer,
+    mode: Mode,
+    adaptive_charset: bool,
+    ascii_only: bool,
+    plain: bool,
+    /// Find blobs every frame, not just while an overlay shows them.
+    collect_centroids: bool,
+    centroids: Vec<(usize, usize)>,
+    color_lookup: Vec<String>,
+    background_lookup: Vec<String>,
+    false_color: [[u8; 3]; 256],
+    /// The `charset` setting the ramp was made from.
+    charset: Option<String>,
+    ramp: Vec<char>,
+    prev_frame: Option<Vec<u8>>,
+    cell_states: Vec<CellHysteresis>,
+    cell_cache: Vec<Option<CachedCell>>,
+    trail: Trail,
+    heatmap: Heatmap,
+}
+
+impl Renderer {
+    pub fn new(colorizer: Colorizer, sobel_threshold: f32) -> Self {
+        Self {
+            sobel_threshold,
+            colorizer,
+            mode: Mode::Ascii,
+            adaptive_charset: false,
+            ascii_only: false,
+            plain: false,
+            collect_centroids: false,
+            centroids: Vec::new(),
+            color_lookup: colorizer.palette(),
+            background_lookup: colorizer.background_palette(),
+            false_color: overlay::false_color_lut(),
+            charset: None,
+            ramp: ASCII_CHARS.to_vec(),
+            prev_frame: None,
+            cell_states: Vec::new(),
+            cell_cache: Vec::new(),
+            trail: Trail::new(),
+            heatmap: Heatmap::new(),
+        }
+    }
+
+    /// Folds every glyph, edges and overlays included, to 7-bit ASCII.
+    pub fn ascii_only(mut self, ascii_only: bool) -> Self {
+        self.ascii_only = ascii_only;
+        self
+    }
+
+    /// Leaves color escapes out of the rows, so they are plain ASCII text.
+    pub fn plain(mut self, plain: bool) -> Self {
+        self.plain = plain;
+        self.ascii_only |= plain;
+        self.color_lookup = self.palette();
+        self.background_lookup = self.backgrounds();
+        self
+    }
+
+    /// Finds the moving blobs of every frame for [`Renderer::centroids`],
+    /// whether or not the blob or tracking overlay is on.
+    pub fn collect_centroids(mut self, collect: bool) -> Self {
+        self.collect_centroids = collect;
+        self
+    }
+
+    /// Motion counted per cell over the session.
+    pub fn heatmap(&self) -> &Heatmap {
+        &self.heatmap
+    }
+
+    /// Centroids of the moving blobs in the last rendered frame, largest
+    /// first, in cells. Empty unless the blob or tracking overlay is on or
+    /// [`Renderer::collect_centroids`] asked for them.
+    pub fn centroids(&self) -> &[(usize, usize)] {
+        &self.centroids
+    }
+
+    fn palette(&self) -> Vec<String> {
+        let palette = self.colorizer.palette();
+        if self.plain {
+            vec![String::new(); palette.len()]
+        } else {
+            palette
+        }
+    }
+
+    fn backgrounds(&self) -> Vec<String> {
+        if self.plain {
+            Vec::new()
+        } else {
+            self.colorizer.background_palette()
+        }
+    }
+
+    /// The colorizer the last frame was rendered with.
+    pub fn colorizer(&self) -> Colorizer {
+        self.colorizer
+    }
+
+    /// Bytes held between frames: lookups, the previous frame, per-cell
+    /// state and caches.
+    pub fn memory_usage(&self) -> usize {
+        let lookups: usize = self
+            .color_lookup
+            .iter()
+            .chain(&self.background_lookup)
+            .map(String::capacity)
+            .sum();
+        lookups
+            + self.prev_frame.as_ref().map_or(0, Vec::capacity)
+            + self.cell_states.capacity() * size_of::<CellHysteresis>()
+            + self.cell_cache.capacity() * size_of::<Option<CachedCell>>()
+            + self.heatmap.memory_usage()
+    }
+
+    /// Frees the previous frame, hysteresis state and cell cache. The next
+    /// frame renders without them: no temporal blending, and glyphs may
+    /// flicker once.
+    pub fn flush_caches(&mut self) {
+        self.prev_frame = None;
+        self.cell_states = Vec::new();
+        self.cell_cache = Vec::new();
+    }
+
+    /// Remembers `frame` as the previous frame without rendering it, for
+    /// when the caller drops a frame to keep up.
+    pub fn skip(&mut self, frame: &Frame) {
+        self.prev_frame = Some(frame.pixels.clone());
+    }
+
+    /// Renders `frame` onto a `cols` x `rows` grid, one string per row.
+    pub fn render(
+        &mut self,
+        frame: &Frame,
+        cols: usize,
+        rows: usize,
+        settings: &RenderSettings,
+    ) -> Result<Vec<String>, RenderError> {
+        if cols == 0 || rows == 0 {
+            return Err(RenderError::EmptyGrid { cols, rows });
+        }
+
+        if settings.colorizer != self.colorizer {
+            self.colorizer = settings.colorizer;
+            self.color_lookup = self.palette();
+            self.background_lookup = self.backgrounds();
+            // Ramp indices and cached glyphs are meaningless once the ramp
+            // changes.
+            self.cell_states.clear();
+            self.cell_cache.clear();
+        }
+
+        if settings.charset != self.charset {
+            self.charset = settings.charset.clone();
+            // A ramp that doesn't check out, say from a hand-edited session
+            // file, falls back to the built-in one.
+            self.ramp = self
+                .charset
+                .as_deref()
+                .and_then(|text| parse_ramp(text).ok())
+                .unwrap_or_else(|| ASCII_CHARS.to_vec());
+            self.cell_states.clear();
+            self.cell_cache.clear();
+        }
+
+        if settings.adaptive_charset != self.adaptive_charset || settings.mode != self.mode {
+            self.adaptive_charset = settings.adaptive_charset;
+            self.mode = settings.mode;
+            self.cell_cache.fill(None);
+        }
+
+        if self.cell_states.len() != cols * rows {
+            self.cell_states.clear();
+            self.cell_states
+                .resize(cols * rows, CellHysteresis::default());
+            self.cell_cache.clear();
+            self.cell_cache.resize(cols * rows, None);
+        }
+
+        let total_pixels = cols * rows;
+        let sobel_sample_rate = if total_pixels > 200_000 {
+            20
+        } else if total_pixels > 100_000 {
+            10
+        } else {
+            1
+        };
+
+        let width = frame.width;
+        let height = frame.height;
+        let decoded = &frame.buffer;
+        let current_pixels = &frame.pixels;
+        let prev_frame = self.prev_frame.as_deref();
+        let colorizer = self.colorizer;
+
+        let ramp: &[char] = if colorizer == Colorizer::HighContrast {
+            &HIGH_CONTRAST_CHARS
+        } else {
+            &self.ramp
+        };
+        // Double-width glyphs cover the cell after them too, which is then
+        // skipped so rows stay `cols` columns wide.
+        let wide_ramp = !self.ascii_only && ramp.iter().any(|&g| is_wide(g));
+        // The high contrast ramp is already made of blocks.
+        let adaptive = settings.adaptive_charset && colorizer != Colorizer::HighContrast;
+        let braille = settings.mode == Mode::Braille;
+        let halfblock = settings.mode == Mode::Halfblock;
+        let quadrant = settings.mode == Mode::Quadrant;
+        let pixel_grid = settings.mode == Mode::Pixel;
+        // Without background colors, blocks are drawn lit or unlit.
+        let backgrounds = !self.background_lookup.is_empty();
+
+        let cell_cache = &self.cell_cache;
+        let sobel_threshold = self.sobel_threshold;
+        let (tile_keys, edges): (Vec<u32>, Vec<EdgeCell>) = (0..cols * rows)
+            .into_par_iter()
+            .map(|i| {
+                let (tx, ty) = (i % cols, i / cols);
+                let (x, y) = cell_origin(tx, ty, width, height, cols, rows);
+                let glyph_mode = !(braille || halfblock || quadrant || pixel_grid);
+                // Which ramp an adaptive cell uses depends on all of it, so
+                // it goes into the key.
+                let flat = adaptive && glyph_mode && {
+                    let (x1, y1) = cell_origin(tx + 1, ty + 1, width, height, cols, rows);
+                    is_flat(cell_variance(current_pixels, width, x, y, x1, y1))
+                };
+                let key = tile_key(current_pixels, prev_frame, x, y, width, height, flat as u32);
+                let edge = match cell_cache[i] {
+                    Some(cached) if cached.key == key => cached.edge,
+                    _ if !glyph_mode => EdgeCell::NONE,
+                    _ if tx % sobel_sample_rate != 0 || ty % sobel_sample_rate != 0 => {
+                        EdgeCell::NONE
+                    }
+                    _ => {
+                        let edge = sobel_detect_edge(decoded, x, y, width, height, sobel_threshold);
+                        let end = cell_origin(tx + 1, ty + 1, width, height, cols, rows);
+                        locate_diagonal(decoded, edge, (x, y), end, width, height)
+                    }
+                };
+                (key, edge)
+            })
+            .unzip();
+
+        let motion = prev_frame
+            .map(|prev| blobs::motion_mask(current_pixels, prev, width, height, cols, rows));
+
+        if let Some(mask) = &motion {
+            self.heatmap.accumulate(mask, cols, rows);
+        }
+
+        let motion_blobs = match &motion {
+            Some(mask) if settings.blobs || settings.tracking || self.collect_centroids => {
+                blobs::find_blobs(mask, cols)
+            }
+            _ => Vec::new(),
+        };
+        self.centroids.clear();
+        self.centroids
+            .extend(motion_blobs.iter().map(|blob| blob.centroid()));
+
+        if settings.tracking {
+            let centroid = motion_blobs.first().map(|blob| blob.centroid());
+            self.trail.push(centroid, cols, rows);
+        }
+
+        let layer = (settings.blobs || settings.tracking).then(|| {
+            let mut layer = Layer::new(cols, rows);
+            if settings.blobs {
+                for blob in &motion_blobs {
+                    layer.draw_box(
+                        blob.min_x,
+                        blob.min_y,
+                        blob.max_x,
+                        blob.max_y,
+                        overlay::BLOB_BOX_COLOR,
+                    );
+                    layer.draw_label(
+                        blob.min_x + 1,
+                        blob.min_y,
+                        &blob.area.to_string(),
+                        overlay::BLOB_BOX_COLOR,
+                        settings.label_dim,
+                    );
+                }
+            }
+            if settings.tracking {
+                self.trail.draw(&mut layer);
+            }
+            layer
+        });
+
+        let mut video = vec![VideoCell::default(); cols * rows];
+        video
+            .par_chunks_mut(cols)
+            .zip(self.cell_states.par_chunks_mut(cols))
+            .zip(self.cell_cache.par_chunks_mut(cols))
+            .enumerate()
+            .for_each(|(ty, ((row_video, row_states), row_cache))| {
+                for tx in 0..cols {
+                    let (x, y) = cell_origin(tx, ty, width, height, cols, rows);
+                    let pixel = decoded.get_pixel(x as u32, y as u32).0;
+                    let key = tile_keys[ty * cols + tx];
+
+                    let mut background = None;
+                    let (color, glyph) = if braille {
+                        braille::cell(decoded, tx, ty, width, height, cols, rows)
+                    } else if halfblock {
+                        let (top, bottom) =
+                            halfblock::cell(decoded, tx, ty, width, height, cols, rows);
+                        if backgrounds {
+                            background = Some(bottom);
+                            (top, halfblock::UPPER_HALF)
+                        } else {
+                            (top, halfblock::glyph(top, bottom))
+                        }
+                    } else if pixel_grid {
+                        let color = pixel::cell(decoded, tx, ty, width, height, cols, rows);
+                        if backgrounds {
+                            background = Some(color);
+                            (color, ' ')
+                        } else {
+                            (color, pixel::glyph(color))
+                        }
+                    } else if quadrant {
+                        let samples = quadrant::samples(decoded, tx, ty, width, height, cols, rows);
+                        if backgrounds {
+                            let (glyph, foreground, back) = quadrant::split(samples);
+                            background = Some(back);
+                            (foreground, glyph)
+                        } else {
+                            quadrant::threshold(samples)
+                        }
+                    } else {
+                        let base = match row_cache[tx] {
+                            Some(cached) if cached.key == key => cached,
+                            _ => {
+                                let previous =
+                                    prev_frame.and_then(|prev| previous_pixel(prev, x, y, width));
+                                let ramp = if adaptive {
+                                    let (x1, y1) =
+                                        cell_origin(tx + 1, ty + 1, width, height, cols, rows);
+                                    let variance =
+                                        cell_variance(current_pixels, width, x, y, x1, y1);
+                                    ramp_for(variance, ramp)
+                                } else {
+                                    ramp
+                                };
+                                let (color, glyph) =
+                                    shade(pixel, previous, &mut row_states[tx], ramp);
+                                let cached = CachedCell {
+                                    key,
+                                    edge: edges[ty * cols + tx],
+                                    color,
+                                    glyph,
+                                };
+                                row_cache[tx] = Some(cached);
+                                cached
+                            }
+                        };
+                        match edge_glyph(&edges, tx, ty, cols) {
+                            Some(glyph) => {
+                                let color = match settings.edge_color {
+                                    EdgeColor::Auto => edge_color(decoded, x, y, width, height),
+                                    EdgeColor::Fixed(color) => color,
+                                };
+                                (color, glyph)
+                            }
+                            None => (base.color, base.glyph),
+                        }
+                    };
+
+                    row_video[tx] = VideoCell {
+                        glyph,
+                        color,
+                        background,
+                    };
+                }
+            });
+
+        // Only colorizers with a palette of colors have anything to dither.
+        if settings.dither == Dither::FloydSteinberg
+            && backgrounds
+            && let Some(quantize) = colorizer.quantizer()
+        {
+            let mut colors: Vec<_> = video.iter().map(|cell| cell.color).collect();
+            dither::floyd_steinberg(&mut colors, cols, &quantize);
+            for (cell, color) in video.iter_mut().zip(colors) {
+                cell.color = color;
+            }
+            // Backgrounds are there for every cell or for none.
+            if video.iter().all(|cell| cell.background.is_some()) {
+                let mut colors: Vec<_> = video.iter().filter_map(|cell| cell.background).collect();
+                dither::floyd_steinberg(&mut colors, cols, &quantize);
+                for (cell, color) in video.iter_mut().zip(colors) {
+                    cell.background = Some(color);
+                }
+            }
+        }
+
+        let ascii_only = self.ascii_only;
+        let color_lookup = &self.color_lookup;
+        let background_lookup = &self.background_lookup;
+        let false_color = &self.false_color;
+        let heatmap = &self.heatmap;
+        let rendered = video
+            .par_chunks(cols)
+            .enumerate()
+            .map(|(ty, row_video)| {
+                let mut row =
+                    RowWriter::new(color_lookup, cols).with_backgrounds(background_lookup);
+
+                let mut covered = false;
+                for (tx, &video_cell) in row_video.iter().enumerate() {
+                    if covered {
+                        covered = false;
+                        continue;
+                    }
+                    let (x, y) = cell_origin(tx, ty, width, height, cols, rows);
+                    let pixel = decoded.get_pixel(x as u32, y as u32).0;
+                    let VideoCell {
+                        glyph: mut ascii_char,
+                        color: [mut r, mut g, mut b],
+                        mut background,
+                    } = video_cell;
+
+                    let luma = overlay::luma(pixel[0], pixel[1], pixel[2]);
+
+                    if settings.view == View::FalseColor {
+                        [r, g, b] = false_color[luma as usize];
+                        background = background
+                            .map(|[r, g, b]| false_color[overlay::luma(r, g, b) as usize]);
+                    }
+
+                    if settings.heatmap
+                        && let Some(heat) = heatmap.color(ty * cols + tx)
+                    {
+                        [r, g, b] = heat;
+                        background = background.map(|_| heat);
+                    }
+
+                    if settings.peaking
+                        && let Some((gx, gy)) = sobel_gradient(decoded, x, y, width, height)
+                        && sobel_magnitude(gx, gy) > settings.peaking_threshold
+                    {
+                        [r, g, b] = overlay::PEAKING_COLORS
+                            [settings.peaking_color % overlay::PEAKING_COLORS.len()];
+                        background = background.map(|_| [r, g, b]);
+                    }
+
+                    if settings.zebra
+                        && let Some((glyph, zebra_color)) = overlay::zebra(tx, ty, luma)
+                    {
+                        ascii_char = glyph;
+                        [r, g, b] = zebra_color;
+                        background = None;
+                    }
+
+                    if let Some(cell) = layer.as_ref().and_then(|l| l.get(tx, ty)) {
+                        (ascii_char, [r, g, b], background) = cell.composite(
+                            ascii_char,
+                            [r, g, b],
+                            background,
+                            settings.overlay_opacity,
+                        );
+                    }
+
+                    if ascii_only {
+                        ascii_char = ascii::fold(ascii_char);
+                    }
+                    if wide_ramp && is_wide(ascii_char) {
+                        // No room for the second column in the last cell.
+                        if tx + 1 == cols {
+                            ascii_char = ' ';
+                        } else {
+                            covered = true;
+                        }
+                    }
+                    match background {
+                        Some([br, bg, bb]) if ascii_char == ' ' => {
+                            row.push_blank(colorizer.index(br, bg, bb))
+                        }
+                        Some([br, bg, bb]) => row.push_pair(
+                            ascii_char,
+                            colorizer.index(r, g, b),
+                            colorizer.index(br, bg, bb),
+                        ),
+                        None => row.push(ascii_char, colorizer.index(r, g, b)),
+                    }
+                }
+
+                row.finish()
+            })
+            .collect();
+
+        self.prev_frame = Some(current_pixels.to_vec());
+        Ok(rendered)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render::charset::BLOCK_CHARS;
+    use crate::source::{ColorSpace, pattern::TestPattern};
+
+    fn grey_frame(value: u8) -> Frame {
+        let buffer = image::ImageBuffer::from_pixel(8, 4, image::Rgb([value; 3]));
+        Frame::new(buffer, "test".into(), ColorSpace::Srgb)
+    }
+
+    #[test]
+    fn renders_one_row_per_grid_line() {
+        let mut renderer = Renderer::new(Colorizer::Monochrome, 30.0);
+        let settings = RenderSettings {
+            colorizer: Colorizer::Monochrome,
+            ..RenderSettings::default()
+        };
+
+        let rows = renderer.render(&grey_frame(0), 4, 2, &settings).unwrap();
+        assert_eq!(rows, ["$$$$", "$$$$"]);
+    }
+
+    #[test]
+    fn colorizer_change_switches_palette() {
+        let mut renderer = Renderer::new(Colorizer::Monochrome, 30.0);
+        let settings = RenderSettings {
+            colorizer: Colorizer::HighContrast,
+            ..RenderSettings::default()
+        };
+
+        let rows = renderer.render(&grey_frame(255), 2, 1, &settings).unwrap();
+        assert_eq!(renderer.colorizer(), Colorizer::HighContrast);
+        assert_eq!(rows, ["\x1b[0;1;97;40m██"]);
+    }
+
+    #[test]
+    fn adaptive_cells_with_the_same_sample_keep_their_own_ramp() {
+        let mut renderer = Renderer::new(Colorizer::Monochrome, 30.0);
+        let settings = RenderSettings {
+            colorizer: Colorizer::Monochrome,
+            adaptive_charset: true,
+            ..RenderSettings::default()
+        };
+        let flat = Frame::new(
+            image::ImageBuffer::from_pixel(8, 8, image::Rgb([128; 3])),
+            "test".into(),
+            ColorSpace::Srgb,
+        );
+        // The same 3x3 around the sample, a checkerboard everywhere else.
+        let busy = Frame::new(
+            image::ImageBuffer::from_fn(8, 8, |x, y| {
+                image::Rgb(match (x < 2 && y < 2, (x + y) % 2) {
+                    (true, _) => [128; 3],
+                    (false, 0) => [0; 3],
+                    (false, _) => [255; 3],
+                })
+            }),
+            "test".into(),
+            ColorSpace::Srgb,
+        );
+
+        renderer.render(&flat, 1, 1, &settings).unwrap();
+        let rows = renderer.render(&flat, 1, 1, &settings).unwrap();
+        assert!(BLOCK_CHARS.contains(&rows[0].chars().next().unwrap()));
+        let rows = renderer.render(&busy, 1, 1, &settings).unwrap();
+        assert!(!BLOCK_CHARS.contains(&rows[0].chars().next().unwrap()));
+    }
+
+    #[test]
+    fn wide_glyphs_take_two_cells() {
+        let mut renderer = Renderer::new(Colorizer::Monochrome, 30.0);
+        let settings = RenderSettings {
+            colorizer: Colorizer::Monochrome,
+            charset: Some(" 木".to_string()),
+            ..RenderSettings::default()
+        };
+
+        let rows = renderer.render(&grey_frame(255), 5, 1, &settings).unwrap();
+        assert_eq!(rows, ["木木 "]);
+    }
+
+    #[test]
+    fn adaptive_charset_draws_flat_regions_with_blocks() {
+        let mut renderer = Renderer::new(Colorizer::Monochrome, 30.0);
+        let mut settings = RenderSettings {
+            colorizer: Colorizer::Monochrome,
+            ..RenderSettings::default()
+        };
+        assert_eq!(
+            renderer.render(&grey_frame(0), 2, 1, &settings).unwrap(),
+            ["$$"]
+        );
+
+        settings.adaptive_charset = true;
+        let rows = renderer.render(&grey_frame(0), 2, 1, &settings).unwrap();
+        assert_eq!(rows, ["██"]);
+    }
+
+    #[test]
+    fn braille_mode_packs_dots() {
+        let mut renderer = Renderer::new(Colorizer::Monochrome, 30.0);
+        let settings = RenderSettings {
+            colorizer: Colorizer::Monochrome,
+            mode: Mode::Braille,
+            ..RenderSettings::default()
+        };
+
+        let rows = renderer.render(&grey_frame(255), 2, 1, &settings).unwrap();
+        assert_eq!(rows, ["\u{28FF}\u{28FF}"]);
+    }
+
+    #[test]
+    fn halfblock_mode_colors_both_halves() {
+        let buffer = image::ImageBuffer::from_fn(1, 2, |_, y| {
+            image::Rgb(if y == 0 { [255, 255, 0] } else { [0, 0, 255] })
+        });
+        let frame = Frame::new(buffer, "test".into(), ColorSpace::Srgb);
+        let settings = RenderSettings {
+            colorizer: Colorizer::TrueColor,
+            mode: Mode::Halfblock,
+            ..RenderSettings::default()
+        };
+
+        let mut renderer = Renderer::new(Colorizer::TrueColor, 30.0);
+        let rows = renderer.render(&frame, 1, 1, &settings).unwrap();
+        assert_eq!(rows, ["\x1b[38;2;255;255;0m\x1b[48;2;0;0;255m▀\x1b[49m"]);
+
+        let mut plain = Renderer::new(Colorizer::TrueColor, 30.0).plain(true);
+        assert_eq!(plain.render(&frame, 1, 1, &settings).unwrap(), ["\""]);
+    }
+
+    #[test]
+    fn pixel_mode_runs_share_one_background_escape() {
+        let buffer = image::ImageBuffer::from_fn(6, 2, |x, _| {
+            image::Rgb(if x < 4 { [255, 0, 0] } else { [0, 0, 255] })
+        });
+        let frame = Frame::new(buffer, "test".into(), ColorSpace::Srgb);
+        let settings = RenderSettings {
+            colorizer: Colorizer::TrueColor,
+            mode: Mode::Pixel,
+            ..RenderSettings::default()
+        };
+
+        let mut renderer = Renderer::new(Colorizer::TrueColor, 30.0);
+        let rows = renderer.render(&frame, 3, 1, &settings).unwrap();
+        assert_eq!(rows, ["\x1b[48;2;255;0;0m  \x1b[48;2;0;0;255m \x1b[49m"]);
+    }
+
+    #[test]
+    fn quadrant_mode_splits_cells_into_two_colors() {
+        let buffer = image::ImageBuffer::from_fn(2, 2, |x, _| {
+            image::Rgb(if x == 0 { [255, 0, 0] } else { [0, 0, 255] })
+        });
+        let frame = Frame::new(buffer, "test".into(), ColorSpace::Srgb);
+        let settings = RenderSettings {
+            colorizer: Colorizer::TrueColor,
+            mode: Mode::Quadrant,
+            ..RenderSettings::default()
+        };
+
+        let mut renderer = Renderer::new(Colorizer::TrueColor, 30.0);
+        let rows = renderer.render(&frame, 1, 1, &settings).unwrap();
+        assert_eq!(rows, ["\x1b[38;2;255;0;0m\x1b[48;2;0;0;255m▌\x1b[49m"]);
+    }
+
+    #[test]
+    fn plain_output_is_bare_ascii() {
+        let mut renderer = Renderer::new(Colorizer::HighContrast, 30.0).plain(true);
+        let settings = RenderSettings {
+            colorizer: Colorizer::HighContrast,
+            ..RenderSettings::default()
+        };
+
+        let rows = renderer.render(&grey_frame(255), 2, 1, &settings).unwrap();
+        assert_eq!(rows, ["@@"]);
+    }
+
+    // Golden output for the built-in test pattern, so changes to the
+    // pipeline show up as a diff here.
+    #[test]
+    fn test_pattern_golden() {
+        let pattern = TestPattern::new(70, 60, 30);
+        let frame = Frame::new(pattern.frame(0), "test".into(), ColorSpace::Srgb);
+        let mut renderer = Renderer::new(Colorizer::Monochrome, 30.0).plain(true);
+        let settings = RenderSettings {
+            colorizer: Colorizer::Monochrome,
+            ..RenderSettings::default()
+        };
+
+        let rows = renderer.render(&frame, 7, 4, &settings).unwrap();
+        assert_eq!(rows, ["nnnqnqq", "_|n|||q", "_|n|||q", "$//+\\+\\"]);
+    }
+
+    #[test]
+    fn empty_grid_is_an_error() {
+        let mut renderer = Renderer::new(Colorizer::Monochrome, 30.0);
+        let result = renderer.render(&grey_frame(0), 0, 2, &RenderSettings::default());
+        assert!(matches!(
+            result,
+            Err(RenderError::EmptyGrid { cols: 0, rows: 2 })
+        ));
+    }
+}
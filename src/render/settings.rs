@@ -0,0 +1,245 @@
+use std::str::FromStr;
+
+use serde::{Deserialize, Serialize};
+
+use super::{color::Colorizer, tone};
+use crate::effects::overlay::{self, View};
+
+/// How cells are drawn.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Mode {
+    /// One glyph from a brightness ramp per cell, with edge glyphs.
+    #[default]
+    Ascii,
+    /// A 2x4 braille dot pattern per cell.
+    Braille,
+    /// An upper half block per cell, the top half in the foreground color
+    /// and the bottom half in the background color.
+    Halfblock,
+    /// A 2x2 quadrant block pattern per cell, split between the foreground
+    /// and background colors.
+    Quadrant,
+    /// A space per cell on a background in the cell's color: a plain pixel
+    /// grid.
+    Pixel,
+}
+
+impl Mode {
+    /// The mode the runtime mode hotkey switches to.
+    pub fn next(self) -> Self {
+        match self {
+            Mode::Ascii => Mode::Braille,
+            Mode::Braille => Mode::Halfblock,
+            Mode::Halfblock => Mode::Quadrant,
+            Mode::Quadrant => Mode::Pixel,
+            Mode::Pixel => Mode::Ascii,
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ascii" => Ok(Mode::Ascii),
+            "braille" => Ok(Mode::Braille),
+            "halfblock" => Ok(Mode::Halfblock),
+            "quadrant" => Ok(Mode::Quadrant),
+            "pixel" => Ok(Mode::Pixel),
+            _ => Err(format!(
+                "unknown mode `{}`; expected ascii, braille, halfblock, quadrant or pixel",
+                s
+            )),
+        }
+    }
+}
+
+/// What color edge glyphs are drawn in.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum EdgeColor {
+    /// The brighter side of the boundary the edge marks, so an outline
+    /// stays visible instead of taking the dark pixel it landed on.
+    #[default]
+    Auto,
+    /// The same color for every edge.
+    Fixed([u8; 3]),
+}
+
+impl FromStr for EdgeColor {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        if s == "auto" {
+            return Ok(EdgeColor::Auto);
+        }
+        let hex = s.strip_prefix('#').unwrap_or(s);
+        let channel = |i: usize| {
+            hex.get(i..i + 2)
+                .and_then(|c| u8::from_str_radix(c, 16).ok())
+        };
+        match (hex.len(), channel(0), channel(2), channel(4)) {
+            (6, Some(r), Some(g), Some(b)) => Ok(EdgeColor::Fixed([r, g, b])),
+            _ => Err(format!(
+                "unknown edge color `{}`; expected auto or a hex color like #ff8800",
+                s
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for EdgeColor {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            EdgeColor::Auto => f.write_str("auto"),
+            EdgeColor::Fixed([r, g, b]) => write!(f, "#{:02x}{:02x}{:02x}", r, g, b),
+        }
+    }
+}
+
+impl TryFrom<String> for EdgeColor {
+    type Error = String;
+
+    fn try_from(s: String) -> Result<Self, Self::Error> {
+        s.parse()
+    }
+}
+
+impl From<EdgeColor> for String {
+    fn from(color: EdgeColor) -> Self {
+        color.to_string()
+    }
+}
+
+/// Error diffusion applied to cell colors before they are matched to the
+/// palette, trading banding for fine noise on colorizers with few colors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum Dither {
+    #[default]
+    None,
+    /// Floyd–Steinberg over the cell grid.
+    #[serde(rename = "fs")]
+    FloydSteinberg,
+}
+
+impl FromStr for Dither {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "none" => Ok(Dither::None),
+            "fs" | "floyd-steinberg" => Ok(Dither::FloydSteinberg),
+            _ => Err(format!("unknown dither `{}`; expected none or fs", s)),
+        }
+    }
+}
+
+/// Settings the input task can change while the render loop is running.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderSettings {
+    pub mode: Mode,
+    pub colorizer: Colorizer,
+    pub view: View,
+    pub zebra: bool,
+    pub blobs: bool,
+    pub tracking: bool,
+    pub heatmap: bool,
+    /// Show the performance HUD over the top-left corner.
+    pub hud: bool,
+    pub peaking: bool,
+    pub peaking_threshold: f32,
+    pub peaking_color: usize,
+    /// Draw flat regions with shading blocks and keep the ASCII ramp for
+    /// detailed ones.
+    pub adaptive_charset: bool,
+    /// Glyph ramp to draw with instead of the built-in one, darkest pixels
+    /// first; see [`super::charset::parse_ramp`].
+    pub charset: Option<String>,
+    pub edge_color: EdgeColor,
+    /// Scales how much overlay glyphs cover the video, from 0 (invisible)
+    /// to 1 (as drawn).
+    pub overlay_opacity: f32,
+    /// How much labels darken the video behind them, from 0 to 1.
+    pub label_dim: f32,
+    pub dither: Dither,
+    /// Added to every channel before rendering, see [`super::tone`].
+    pub brightness: i16,
+    /// Scales every channel's distance from mid grey before rendering.
+    pub contrast: f32,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Ascii,
+            colorizer: Colorizer::detect(),
+            view: View::Normal,
+            zebra: false,
+            blobs: false,
+            tracking: false,
+            heatmap: false,
+            hud: false,
+            peaking: false,
+            peaking_threshold: overlay::PEAKING_DEFAULT_THRESHOLD,
+            peaking_color: 0,
+            adaptive_charset: false,
+            charset: None,
+            edge_color: EdgeColor::Auto,
+            overlay_opacity: 1.0,
+            label_dim: overlay::DEFAULT_LABEL_DIM,
+            dither: Dither::None,
+            brightness: 0,
+            contrast: 1.0,
+        }
+    }
+}
+
+impl RenderSettings {
+    /// Drops to monochrome when NO_COLOR is set: it applies to this run
+    /// whatever the settings were loaded from, a session or a watermark.
+    pub fn honor_no_color(&mut self) {
+        if Colorizer::detect() == Colorizer::Monochrome {
+            self.colorizer = Colorizer::Monochrome;
+        }
+    }
+
+    /// Checks the values the command line would have rejected, for settings
+    /// read from a file: a session, a watermark or a macro.
+    pub fn validate(&self) -> Result<(), String> {
+        if self.peaking_color >= overlay::PEAKING_COLORS.len() {
+            return Err(format!(
+                "peaking_color {} out of range; expected 0 to {}",
+                self.peaking_color,
+                overlay::PEAKING_COLORS.len() - 1
+            ));
+        }
+        for (key, value) in [
+            ("overlay_opacity", self.overlay_opacity),
+            ("label_dim", self.label_dim),
+        ] {
+            if !(0.0..=1.0).contains(&value) {
+                return Err(format!("{} {} out of range; expected 0 to 1", key, value));
+            }
+        }
+        if self.brightness.abs() > tone::MAX_BRIGHTNESS {
+            return Err(format!(
+                "brightness {} out of range; expected -{1} to {1}",
+                self.brightness,
+                tone::MAX_BRIGHTNESS
+            ));
+        }
+        if !(tone::MIN_CONTRAST..=tone::MAX_CONTRAST).contains(&self.contrast) {
+            return Err(format!(
+                "contrast {} out of range; expected {} to {}",
+                self.contrast,
+                tone::MIN_CONTRAST,
+                tone::MAX_CONTRAST
+            ));
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,187 @@
+//! Brightness and contrast: a 256-entry lookup table every channel of the
+//! frame goes through before it is rendered or drawn as an image.
+
+use std::time::{Duration, Instant};
+
+use crate::source::Frame;
+
+/// How far one brightness key press moves each channel.
+pub const BRIGHTNESS_STEP: i16 = 8;
+pub const MAX_BRIGHTNESS: i16 = 128;
+/// How far one contrast key press scales the distance from mid grey.
+pub const CONTRAST_STEP: f32 = 0.1;
+pub const MIN_CONTRAST: f32 = 0.2;
+pub const MAX_CONTRAST: f32 = 3.0;
+
+/// How long the indicator stays up after the values change.
+pub const INDICATOR_DURATION: Duration = Duration::from_secs(2);
+
+/// Brightness and contrast for a stream of frames: the table for the
+/// current values, the adjusted frame, kept for its buffers, and when the
+/// values last changed, for the indicator.
+pub struct Tone {
+    values: Option<(i16, f32)>,
+    lut: [u8; 256],
+    changed_at: Option<Instant>,
+    adjusted: Option<Frame>,
+}
+
+impl Default for Tone {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Tone {
+    pub fn new() -> Self {
+        Self {
+            values: None,
+            lut: lut(0, 1.0),
+            changed_at: None,
+            adjusted: None,
+        }
+    }
+
+    /// Switches to `brightness` and `contrast`, rebuilding the table when
+    /// they changed.
+    pub fn set(&mut self, brightness: i16, contrast: f32) {
+        if self.values == Some((brightness, contrast)) {
+            return;
+        }
+        // Values the first frame starts with come from the command line or
+        // the session; only later changes are worth pointing out.
+        if self.values.is_some() {
+            self.changed_at = Some(Instant::now());
+        }
+        self.values = Some((brightness, contrast));
+        self.lut = lut(brightness, contrast);
+    }
+
+    /// `frame` with the current values applied, or `None` when they leave
+    /// it as it is.
+    pub fn apply(&mut self, frame: &Frame) -> Option<&Frame> {
+        let (brightness, contrast) = self.values.unwrap_or((0, 1.0));
+        if is_neutral(brightness, contrast) {
+            return None;
+        }
+        let target = self.adjusted.get_or_insert_with(|| {
+            Frame::new(
+                image::RgbImage::new(0, 0),
+                frame.source.clone(),
+                frame.color_space,
+            )
+        });
+        adjust(&self.lut, frame, target);
+        Some(target)
+    }
+
+    /// What the indicator shows, for [`INDICATOR_DURATION`] after the
+    /// values change.
+    pub fn indicator(&self) -> Option<String> {
+        let (brightness, contrast) = self.values?;
+        self.changed_at
+            .is_some_and(|at| at.elapsed() < INDICATOR_DURATION)
+            .then(|| describe(brightness, contrast))
+    }
+}
+
+/// Whether `brightness` and `contrast` leave every value as it is.
+pub fn is_neutral(brightness: i16, contrast: f32) -> bool {
+    brightness == 0 && contrast == 1.0
+}
+
+/// `brightness` moved `steps` key presses, kept within
+/// ±[`MAX_BRIGHTNESS`].
+pub fn step_brightness(brightness: i16, steps: i16) -> i16 {
+    (brightness + steps * BRIGHTNESS_STEP).clamp(-MAX_BRIGHTNESS, MAX_BRIGHTNESS)
+}
+
+/// `contrast` moved `steps` key presses, rounded to a tenth so stepping
+/// back lands on 1 exactly.
+pub fn step_contrast(contrast: f32, steps: i16) -> f32 {
+    let stepped = ((contrast + steps as f32 * CONTRAST_STEP) * 10.0).round() / 10.0;
+    stepped.clamp(MIN_CONTRAST, MAX_CONTRAST)
+}
+
+/// The table for `brightness`, added to each channel, and `contrast`, which
+/// scales its distance from mid grey first.
+pub fn lut(brightness: i16, contrast: f32) -> [u8; 256] {
+    std::array::from_fn(|v| {
+        let adjusted = (v as f32 - 128.0) * contrast + 128.0 + brightness as f32;
+        adjusted.round().clamp(0.0, 255.0) as u8
+    })
+}
+
+/// Makes `target` a copy of `frame` with every channel mapped through
+/// `lut`, reusing its buffers.
+pub fn adjust(lut: &[u8; 256], frame: &Frame, target: &mut Frame) {
+    if target.buffer.dimensions() != frame.buffer.dimensions() {
+        target.buffer = image::RgbImage::new(frame.buffer.width(), frame.buffer.height());
+    }
+    for (out, &value) in target.buffer.iter_mut().zip(frame.buffer.as_raw()) {
+        *out = lut[value as usize];
+    }
+    target.pixels.clear();
+    target.pixels.extend_from_slice(target.buffer.as_raw());
+    target.width = frame.width;
+    target.height = frame.height;
+    target.captured_at = frame.captured_at;
+    target.sequence = frame.sequence;
+    target.source = frame.source.clone();
+    target.color_space = frame.color_space;
+}
+
+/// The values as the on-screen indicator shows them, e.g.
+/// `brightness +16  contrast 1.2x`.
+pub fn describe(brightness: i16, contrast: f32) -> String {
+    format!("brightness {:+}  contrast {:.1}x", brightness, contrast)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lut_shifts_and_stretches_around_mid_grey() {
+        assert_eq!(lut(0, 1.0), std::array::from_fn(|v| v as u8));
+        let brighter = lut(16, 1.0);
+        assert_eq!((brighter[0], brighter[100], brighter[250]), (16, 116, 255));
+        let contrasty = lut(0, 2.0);
+        assert_eq!(
+            (contrasty[64], contrasty[128], contrasty[192]),
+            (0, 128, 255)
+        );
+        assert_eq!(describe(-8, 1.2), "brightness -8  contrast 1.2x");
+    }
+
+    #[test]
+    fn steps_stay_in_range_and_return_to_neutral() {
+        assert_eq!(step_brightness(124, 1), MAX_BRIGHTNESS);
+        assert_eq!(step_brightness(0, -2), -16);
+        let up = (0..3).fold(1.0, |c, _| step_contrast(c, 1));
+        assert!(is_neutral(0, (0..3).fold(up, |c, _| step_contrast(c, -1))));
+        assert_eq!(step_contrast(0.2, -1), MIN_CONTRAST);
+    }
+
+    #[test]
+    fn only_later_changes_show_the_indicator() {
+        let frame = Frame::new(
+            image::RgbImage::from_pixel(1, 1, image::Rgb([100; 3])),
+            "test".into(),
+            crate::source::ColorSpace::Srgb,
+        );
+        let mut tone = Tone::new();
+        tone.set(0, 1.0);
+        assert!(tone.apply(&frame).is_none());
+        assert_eq!(tone.indicator(), None);
+
+        tone.set(16, 1.0);
+        let adjusted = tone.apply(&frame).unwrap();
+        assert_eq!(adjusted.pixels, [116; 3]);
+        assert_eq!(adjusted.buffer.as_raw(), &[116; 3]);
+        assert_eq!(
+            tone.indicator().as_deref(),
+            Some("brightness +16  contrast 1.0x")
+        );
+    }
+}
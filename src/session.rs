@@ -0,0 +1,151 @@
+use std::{
+    error::Error,
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::watch;
+use webcii::render::settings::RenderSettings;
+
+use crate::config::Config;
+
+/// Runtime state kept across launches with `--session`.
+#[derive(Serialize, Deserialize)]
+pub struct Session {
+    #[serde(flatten)]
+    pub config: Config,
+    pub settings: RenderSettings,
+}
+
+impl Session {
+    pub fn capture(config: &Config, settings: &RenderSettings) -> Self {
+        Self {
+            config: config.clone(),
+            settings: settings.clone(),
+        }
+    }
+
+    /// Reads a session file. A missing file just means there is nothing to
+    /// restore yet.
+    pub fn load(path: &Path) -> Result<Option<Self>, Box<dyn Error>> {
+        match fs::read_to_string(path) {
+            Ok(text) => {
+                let session: Self = toml::from_str(&text)?;
+                session.settings.validate()?;
+                Ok(Some(session))
+            }
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: &Path) -> Result<(), Box<dyn Error>> {
+        fs::write(path, toml::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Replaces `config` with the saved one and returns the saved render
+    /// settings. Command-line flags are applied on top afterwards.
+    pub fn restore(self, config: &mut Config) -> RenderSettings {
+        *config = self.config;
+
+        let mut settings = self.settings;
+        settings.honor_no_color();
+        settings
+    }
+}
+
+/// Saves the session when dropped, so it is kept on every way out: a
+/// quit, an error return or a panic. The camera saved is the one in use,
+/// which after a failover needn't be the configured one.
+pub struct SaveOnExit {
+    path: PathBuf,
+    config: Config,
+    settings: watch::Receiver<RenderSettings>,
+    camera: watch::Receiver<u32>,
+}
+
+impl SaveOnExit {
+    pub fn new(
+        path: &Path,
+        config: &Config,
+        settings: watch::Receiver<RenderSettings>,
+        camera: watch::Receiver<u32>,
+    ) -> Self {
+        Self {
+            path: path.to_path_buf(),
+            config: config.clone(),
+            settings,
+            camera,
+        }
+    }
+}
+
+impl Drop for SaveOnExit {
+    fn drop(&mut self) {
+        let config = Config {
+            camera: *self.camera.borrow(),
+            ..self.config.clone()
+        };
+        if let Err(e) = Session::capture(&config, &self.settings.borrow()).save(&self.path) {
+            eprintln!("webcii: {}: {}", self.path.display(), e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trips_through_toml() {
+        let session = Session {
+            config: Config {
+                camera: 5,
+                ..Config::default()
+            },
+            settings: RenderSettings {
+                zebra: true,
+                ..RenderSettings::default()
+            },
+        };
+        let text = toml::to_string_pretty(&session).unwrap();
+        assert!(text.starts_with("camera = 5\n"));
+
+        let mut config = Config::default();
+        let settings = toml::from_str::<Session>(&text)
+            .unwrap()
+            .restore(&mut config);
+        assert_eq!(config.camera, 5);
+        assert!(settings.zebra);
+    }
+
+    #[test]
+    fn dropping_saves_the_camera_in_use() {
+        let path = std::env::temp_dir().join(format!("webcii-session-{}.toml", std::process::id()));
+        let (settings_tx, settings_rx) = watch::channel(RenderSettings::default());
+        let (camera_tx, camera_rx) = watch::channel(0);
+        let saver = SaveOnExit::new(&path, &Config::default(), settings_rx, camera_rx);
+        settings_tx.send_modify(|settings| settings.zebra = true);
+        camera_tx.send_replace(2);
+        drop(saver);
+
+        let session = Session::load(&path).unwrap().unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(session.config.camera, 2);
+        assert!(session.settings.zebra);
+    }
+
+    #[test]
+    fn out_of_range_settings_are_rejected() {
+        let path = std::env::temp_dir().join(format!("webcii-bad-{}.toml", std::process::id()));
+        fs::write(&path, "[settings]\ncontrast = 9.0\n").unwrap();
+        let err = Session::load(&path).err().unwrap();
+        let _ = fs::remove_file(&path);
+        assert_eq!(
+            err.to_string(),
+            "contrast 9 out of range; expected 0.2 to 3"
+        );
+    }
+}
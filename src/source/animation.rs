@@ -0,0 +1,178 @@
+use std::{
+    fs::File,
+    io::BufReader,
+    path::Path,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use image::{AnimationDecoder, DynamicImage, codecs::gif::GifDecoder};
+
+use super::{ColorSpace, Frame, FrameSource};
+use crate::error::{ImageError, WebciiError};
+
+/// Delays this short are treated as unset, the way browsers do; files
+/// written with 0 or 10 ms delays expect roughly 10 frames per second.
+const MIN_FRAME_DELAY: Duration = Duration::from_millis(20);
+const DEFAULT_FRAME_DELAY: Duration = Duration::from_millis(100);
+
+/// An animated GIF, decoded up front and played back with its per-frame
+/// delays.
+pub struct GifSource {
+    frames: Vec<(image::RgbImage, Duration)>,
+    id: Arc<str>,
+    /// Total number of times to play the animation; `None` loops forever.
+    plays: Option<u32>,
+    played: u32,
+    index: usize,
+    next_due: Option<Instant>,
+}
+
+impl GifSource {
+    /// Opens `path`. `plays` overrides the loop count stored in the file:
+    /// the number of times to play it, with `Some(0)` meaning forever.
+    pub fn open(path: &Path, plays: Option<u32>) -> Result<Self, ImageError> {
+        let load = |source| ImageError::Load {
+            path: path.to_path_buf(),
+            source,
+        };
+        let open = || File::open(path).map(BufReader::new);
+
+        let plays = match plays {
+            Some(0) => None,
+            Some(plays) => Some(plays),
+            None => file_plays(open().map_err(|e| load(e.into()))?).map_err(|source| {
+                ImageError::Gif {
+                    path: path.to_path_buf(),
+                    source,
+                }
+            })?,
+        };
+
+        let decoder = GifDecoder::new(open().map_err(|e| load(e.into()))?).map_err(load)?;
+        let frames = decoder
+            .into_frames()
+            .map(|frame| {
+                let frame = frame?;
+                let delay = Duration::from(frame.delay());
+                let delay = if delay < MIN_FRAME_DELAY {
+                    DEFAULT_FRAME_DELAY
+                } else {
+                    delay
+                };
+                Ok((DynamicImage::from(frame.into_buffer()).into_rgb8(), delay))
+            })
+            .collect::<Result<Vec<_>, _>>()
+            .map_err(load)?;
+
+        Ok(Self {
+            frames,
+            id: path
+                .file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into(),
+            plays,
+            played: 0,
+            index: 0,
+            next_due: None,
+        })
+    }
+}
+
+impl FrameSource for GifSource {
+    fn next_frame(&mut self) -> Result<Option<Frame>, WebciiError> {
+        if self.index == self.frames.len() {
+            self.played += 1;
+            self.index = 0;
+        }
+        if self.frames.is_empty() || self.plays.is_some_and(|plays| self.played >= plays) {
+            return Ok(None);
+        }
+
+        // Each frame is due when the previous one's delay has run out,
+        // measured from the schedule rather than from when it was shown,
+        // so slow frames don't stretch the whole animation.
+        let now = Instant::now();
+        let due = self.next_due.unwrap_or(now);
+        if let Some(wait) = due.checked_duration_since(now) {
+            thread::sleep(wait);
+        }
+
+        let (buffer, delay) = &self.frames[self.index];
+        self.next_due = Some(due + *delay);
+        self.index += 1;
+        Ok(Some(Frame::new(
+            buffer.clone(),
+            self.id.clone(),
+            ColorSpace::Srgb,
+        )))
+    }
+}
+
+/// How many times the file asks to be played, from its NETSCAPE2.0 loop
+/// extension: `None` for forever, once when there is no extension.
+fn file_plays(reader: BufReader<File>) -> Result<Option<u32>, gif::DecodingError> {
+    let mut decoder = gif::DecodeOptions::new().read_info(reader)?;
+    // The loop extension sits between the header and the first frame.
+    decoder.next_frame_info()?;
+    Ok(match decoder.repeat() {
+        gif::Repeat::Infinite => None,
+        gif::Repeat::Finite(repeats) => Some(u32::from(repeats) + 1),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source(frames: usize, plays: Option<u32>) -> GifSource {
+        GifSource {
+            frames: vec![(image::RgbImage::new(1, 1), Duration::ZERO); frames],
+            id: "test".into(),
+            plays,
+            played: 0,
+            index: 0,
+            next_due: None,
+        }
+    }
+
+    #[test]
+    fn finite_loop_count_ends_the_stream() {
+        let mut gif = source(2, Some(2));
+        for _ in 0..4 {
+            assert!(gif.next_frame().unwrap().is_some());
+        }
+        assert!(gif.next_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn file_loop_count_and_delays_are_read() {
+        let path = std::env::temp_dir().join(format!("webcii-{}.gif", std::process::id()));
+        {
+            let mut encoder = gif::Encoder::new(File::create(&path).unwrap(), 2, 1, &[]).unwrap();
+            encoder.set_repeat(gif::Repeat::Finite(1)).unwrap();
+            for (value, delay) in [(0, 5), (255, 0)] {
+                let mut frame = gif::Frame::from_rgb(2, 1, &[value; 6]);
+                frame.delay = delay;
+                encoder.write_frame(&frame).unwrap();
+            }
+        }
+
+        let gif = GifSource::open(&path, None).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(gif.plays, Some(2));
+        let delays: Vec<_> = gif.frames.iter().map(|(_, delay)| *delay).collect();
+        assert_eq!(delays, [Duration::from_millis(50), DEFAULT_FRAME_DELAY]);
+        assert_eq!(gif.frames[1].0.get_pixel(0, 0).0, [255; 3]);
+    }
+
+    #[test]
+    fn no_loop_count_keeps_playing() {
+        let mut gif = source(1, None);
+        for _ in 0..10 {
+            assert!(gif.next_frame().unwrap().is_some());
+        }
+    }
+}
@@ -0,0 +1,64 @@
+use std::sync::Arc;
+
+use nokhwa::{Camera, pixel_format::RgbFormat, utils::RequestedFormatType};
+use tokio::sync::watch;
+
+use super::{
+    ColorSpace, Frame, FrameSource, devices,
+    hotplug::{Listing, is_attached},
+};
+use crate::error::{CameraError, WebciiError};
+
+/// A webcam opened through nokhwa.
+pub struct CameraSource {
+    camera: Camera,
+    index: u32,
+    id: Arc<str>,
+    devices: Option<watch::Receiver<Listing>>,
+}
+
+impl CameraSource {
+    /// Opens camera `index` with the first format in `chain` it accepts; see
+    /// [`devices::negotiation_chain`].
+    pub fn open(index: u32, chain: &[RequestedFormatType]) -> Result<Self, CameraError> {
+        Ok(Self {
+            camera: devices::open(index, chain)?,
+            index,
+            id: format!("cam{}", index).into(),
+            devices: None,
+        })
+    }
+
+    /// Ends the stream instead of reporting frame errors once the camera is
+    /// gone from `devices`, a listing from [`super::hotplug::monitor`].
+    pub fn ends_when_unplugged(mut self, devices: watch::Receiver<Listing>) -> Self {
+        self.devices = Some(devices);
+        self
+    }
+}
+
+impl FrameSource for CameraSource {
+    fn next_frame(&mut self) -> Result<Option<Frame>, WebciiError> {
+        let frame = match self.camera.frame() {
+            Ok(frame) => frame,
+            Err(_)
+                if self
+                    .devices
+                    .as_ref()
+                    .is_some_and(|devices| !is_attached(&devices.borrow(), self.index)) =>
+            {
+                return Ok(None);
+            }
+            Err(e) => return Err(CameraError::Frame(e).into()),
+        };
+        let decoded = frame
+            .decode_image::<RgbFormat>()
+            .map_err(CameraError::Decode)?;
+        // nokhwa converts to RGB without saying which matrix it used.
+        Ok(Some(Frame::new(
+            decoded,
+            self.id.clone(),
+            ColorSpace::Unknown,
+        )))
+    }
+}
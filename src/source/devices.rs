@@ -0,0 +1,238 @@
+use std::fmt::Write;
+
+use nokhwa::{
+    Camera, NokhwaError,
+    pixel_format::RgbFormat,
+    utils::{
+        ApiBackend, CameraFormat, CameraIndex, FrameFormat, RequestedFormat, RequestedFormatType,
+        Resolution,
+    },
+};
+
+use crate::error::CameraError;
+
+/// Frame rate asked for when only a resolution is requested.
+const DEFAULT_FPS: u32 = 30;
+/// Encodings tried, in order, when asking for an exact or closest format.
+const PREFERRED_ENCODINGS: [FrameFormat; 2] = [FrameFormat::MJPEG, FrameFormat::YUYV];
+
+/// One attached camera as shown by `--list-cameras`.
+pub struct Device {
+    pub index: String,
+    pub name: String,
+    /// Supported formats, or why they couldn't be read.
+    pub formats: Result<Vec<CameraFormat>, String>,
+}
+
+/// Asks the OS for camera access where that is needed (macOS), blocking
+/// until the user answers the prompt.
+pub fn request_camera_permission() -> Result<(), CameraError> {
+    if nokhwa::nokhwa_check() {
+        return Ok(());
+    }
+
+    let (tx, rx) = std::sync::mpsc::channel();
+    nokhwa::nokhwa_initialize(move |granted| {
+        let _ = tx.send(granted);
+    });
+    if rx.recv().unwrap_or(false) {
+        Ok(())
+    } else {
+        Err(CameraError::PermissionDenied)
+    }
+}
+
+/// Enumerates attached cameras through the platform's native backend and
+/// briefly opens each one to read the formats it supports.
+pub fn query() -> Result<Vec<Device>, CameraError> {
+    let backend = nokhwa::native_api_backend().unwrap_or(ApiBackend::Auto);
+    let cameras = nokhwa::query(backend).map_err(CameraError::Query)?;
+
+    Ok(cameras
+        .into_iter()
+        .map(|info| {
+            let requested = RequestedFormat::new::<RgbFormat>(RequestedFormatType::None);
+            let formats = Camera::new(info.index().clone(), requested)
+                .and_then(|mut camera| camera.compatible_camera_formats())
+                .map(|mut formats| {
+                    formats.sort_by_key(|f| (f.format(), f.width(), f.height(), f.frame_rate()));
+                    formats.dedup();
+                    formats
+                })
+                .map_err(|e| e.to_string());
+            Device {
+                index: info.index().to_string(),
+                name: info.human_name(),
+                formats,
+            }
+        })
+        .collect())
+}
+
+/// Attached cameras as (index, name), without opening them, so it is
+/// cheap enough to call repeatedly.
+pub fn list() -> Result<Vec<(u32, String)>, CameraError> {
+    let backend = nokhwa::native_api_backend().unwrap_or(ApiBackend::Auto);
+    let cameras = nokhwa::query(backend).map_err(CameraError::Query)?;
+    Ok(cameras
+        .into_iter()
+        .filter_map(|info| Some((info.index().as_index().ok()?, info.human_name())))
+        .collect())
+}
+
+/// Format requests to try, most specific first, for an optional
+/// `width` x `height` resolution and frame rate. Drivers reject formats they
+/// don't list, so every chain ends with whatever the camera does fastest.
+pub fn negotiation_chain(
+    resolution: Option<(u32, u32)>,
+    fps: Option<u32>,
+) -> Vec<RequestedFormatType> {
+    let mut chain = Vec::new();
+
+    if let Some((width, height)) = resolution {
+        let resolution = Resolution::new(width, height);
+        let rate = fps.unwrap_or(DEFAULT_FPS);
+        if fps.is_some() {
+            chain.extend(PREFERRED_ENCODINGS.map(|encoding| {
+                RequestedFormatType::Exact(CameraFormat::new(resolution, encoding, rate))
+            }));
+        }
+        chain.extend(PREFERRED_ENCODINGS.map(|encoding| {
+            RequestedFormatType::Closest(CameraFormat::new(resolution, encoding, rate))
+        }));
+        chain.push(RequestedFormatType::HighestResolution(resolution));
+    }
+    if let Some(fps) = fps {
+        chain.push(RequestedFormatType::HighestFrameRate(fps));
+    }
+
+    chain.push(RequestedFormatType::AbsoluteHighestFrameRate);
+    chain
+}
+
+/// Opens camera `index` and starts its stream with the first request in
+/// `chain` the driver accepts, returning the last error if none is.
+pub fn open(index: u32, chain: &[RequestedFormatType]) -> Result<Camera, CameraError> {
+    let mut last_error = None;
+    for &request in chain {
+        let requested = RequestedFormat::new::<RgbFormat>(request);
+        let opened = Camera::new(CameraIndex::Index(index), requested).and_then(|mut camera| {
+            camera.open_stream()?;
+            Ok(camera)
+        });
+        match opened {
+            Ok(camera) => return Ok(camera),
+            Err(e) => last_error = Some(e),
+        }
+    }
+    let source = last_error.unwrap_or_else(|| {
+        NokhwaError::OpenDeviceError(index.to_string(), "no format requested".to_string())
+    });
+    Err(CameraError::Open { index, source })
+}
+
+/// Plain-text table with one line per supported format; the index and name
+/// are only printed on a camera's first line.
+pub fn format_table(devices: &[Device]) -> String {
+    if devices.is_empty() {
+        return "No cameras found.\n".to_string();
+    }
+
+    let name_width = devices
+        .iter()
+        .map(|d| d.name.chars().count())
+        .chain(["NAME".len()])
+        .max()
+        .unwrap_or(0);
+
+    let mut table = String::new();
+    let _ = writeln!(table, "{:<5}  {:<name_width$}  FORMAT", "INDEX", "NAME");
+    for device in devices {
+        let formats = match &device.formats {
+            Ok(formats) if formats.is_empty() => vec!["(none reported)".to_string()],
+            Ok(formats) => formats
+                .iter()
+                .map(|f| format!("{} {}@{}fps", f.format(), f.resolution(), f.frame_rate()))
+                .collect(),
+            Err(e) => vec![format!("(unavailable: {})", e)],
+        };
+        for (i, format) in formats.iter().enumerate() {
+            let (index, name) = if i == 0 {
+                (device.index.as_str(), device.name.as_str())
+            } else {
+                ("", "")
+            };
+            let _ = writeln!(table, "{:<5}  {:<name_width$}  {}", index, name, format);
+        }
+    }
+    table
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn table_lists_each_format_once_per_line() {
+        let devices = [
+            Device {
+                index: "0".to_string(),
+                name: "FaceTime HD Camera".to_string(),
+                formats: Ok(vec![
+                    CameraFormat::new(Resolution::new(640, 480), FrameFormat::MJPEG, 30),
+                    CameraFormat::new(Resolution::new(1280, 720), FrameFormat::MJPEG, 30),
+                ]),
+            },
+            Device {
+                index: "1".to_string(),
+                name: "Capture".to_string(),
+                formats: Err("busy".to_string()),
+            },
+        ];
+
+        assert_eq!(
+            format_table(&devices),
+            "INDEX  NAME                FORMAT\n\
+             0      FaceTime HD Camera  MJPEG 640x480@30fps\n\
+             \x20                          MJPEG 1280x720@30fps\n\
+             1      Capture             (unavailable: busy)\n"
+        );
+    }
+
+    #[test]
+    fn chain_relaxes_towards_any_format() {
+        assert_eq!(
+            negotiation_chain(None, None),
+            [RequestedFormatType::AbsoluteHighestFrameRate]
+        );
+        assert_eq!(
+            negotiation_chain(None, Some(15)),
+            [
+                RequestedFormatType::HighestFrameRate(15),
+                RequestedFormatType::AbsoluteHighestFrameRate,
+            ]
+        );
+
+        let chain = negotiation_chain(Some((640, 480)), Some(30));
+        let exact = CameraFormat::new(Resolution::new(640, 480), FrameFormat::MJPEG, 30);
+        assert_eq!(chain[0], RequestedFormatType::Exact(exact));
+        assert_eq!(chain[2], RequestedFormatType::Closest(exact));
+        assert_eq!(chain.len(), 7);
+        assert_eq!(
+            chain.last(),
+            Some(&RequestedFormatType::AbsoluteHighestFrameRate)
+        );
+    }
+
+    #[test]
+    fn resolution_alone_skips_exact_requests() {
+        let chain = negotiation_chain(Some((1280, 720)), None);
+        assert!(matches!(chain[0], RequestedFormatType::Closest(_)));
+        assert_eq!(chain.len(), 4);
+    }
+
+    #[test]
+    fn empty_table_says_so() {
+        assert_eq!(format_table(&[]), "No cameras found.\n");
+    }
+}
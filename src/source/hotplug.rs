@@ -0,0 +1,132 @@
+//! Camera hotplug by periodic re-enumeration: works the same on every
+//! platform nokhwa supports, without udev, IOKit or WMI bindings of our own.
+
+use std::{fmt, thread, time::Duration};
+
+use tokio::sync::watch;
+
+use crate::error::CameraError;
+
+/// How often the attached cameras are listed again.
+pub const POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Attached cameras as (index, name), or why they couldn't be listed.
+pub type Listing = Result<Vec<(u32, String)>, String>;
+
+/// A camera that appeared or went away between two listings.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Change {
+    Connected { index: u32, name: String },
+    Disconnected { index: u32, name: String },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Connected { index, name } => write!(f, "camera {} ({}) connected", index, name),
+            Change::Disconnected { index, name } => {
+                write!(f, "camera {} ({}) disconnected", index, name)
+            }
+        }
+    }
+}
+
+/// Lists cameras with `list` now and then every [`POLL_INTERVAL`] on a
+/// thread of its own, publishing each new listing and calling `notify`
+/// for every camera that came or went. The thread stops once every
+/// receiver is gone.
+pub fn monitor<L, N>(mut list: L, mut notify: N) -> watch::Receiver<Listing>
+where
+    L: FnMut() -> Result<Vec<(u32, String)>, CameraError> + Send + 'static,
+    N: FnMut(Change) + Send + 'static,
+{
+    let (tx, rx) = watch::channel(list().map_err(|e| e.to_string()));
+    thread::spawn(move || {
+        loop {
+            thread::sleep(POLL_INTERVAL);
+            if tx.is_closed() {
+                break;
+            }
+            let current = list().map_err(|e| e.to_string());
+            tx.send_if_modified(|shown| {
+                if *shown == current {
+                    return false;
+                }
+                changes(shown, &current).into_iter().for_each(&mut notify);
+                *shown = current;
+                true
+            });
+        }
+    });
+    rx
+}
+
+/// Cameras in `after` that weren't in `before`, then the ones that went. A
+/// listing that failed counts as no cameras.
+pub fn changes(before: &Listing, after: &Listing) -> Vec<Change> {
+    let (before, after) = (
+        before.as_deref().unwrap_or_default(),
+        after.as_deref().unwrap_or_default(),
+    );
+    let connected = after
+        .iter()
+        .filter(|camera| !before.contains(camera))
+        .map(|(index, name)| Change::Connected {
+            index: *index,
+            name: name.clone(),
+        });
+    let disconnected =
+        before
+            .iter()
+            .filter(|camera| !after.contains(camera))
+            .map(|(index, name)| Change::Disconnected {
+                index: *index,
+                name: name.clone(),
+            });
+    connected.chain(disconnected).collect()
+}
+
+/// Whether camera `index` is in `listing`.
+pub fn is_attached(listing: &Listing, index: u32) -> bool {
+    listing
+        .as_deref()
+        .is_ok_and(|cameras| cameras.iter().any(|&(i, _)| i == index))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn camera(index: u32, name: &str) -> (u32, String) {
+        (index, name.to_string())
+    }
+
+    #[test]
+    fn changes_name_cameras_that_came_and_went() {
+        let before = Ok(vec![camera(0, "Built-in"), camera(1, "USB")]);
+        let after = Ok(vec![camera(0, "Built-in"), camera(2, "Capture")]);
+        let changes = changes(&before, &after);
+        assert_eq!(
+            changes,
+            [
+                Change::Connected {
+                    index: 2,
+                    name: "Capture".to_string()
+                },
+                Change::Disconnected {
+                    index: 1,
+                    name: "USB".to_string()
+                },
+            ]
+        );
+        assert_eq!(changes[1].to_string(), "camera 1 (USB) disconnected");
+    }
+
+    #[test]
+    fn failed_listing_counts_as_empty() {
+        let before = Ok(vec![camera(0, "Built-in")]);
+        assert_eq!(super::changes(&before, &Err("busy".to_string())).len(), 1);
+        assert!(is_attached(&before, 0));
+        assert!(!is_attached(&Err("busy".to_string()), 0));
+    }
+}
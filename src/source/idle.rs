@@ -0,0 +1,187 @@
+use tokio::sync::watch;
+
+use super::{
+    ColorSpace, Frame, FrameSource,
+    hotplug::{Listing, is_attached},
+    pattern::TestPattern,
+};
+
+/// The card doesn't need the test pattern's full frame rate.
+const CARD_FPS: u32 = 15;
+
+/// What the idle card shows while webcii waits for a camera.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Waiting {
+    /// The camera webcii was asked to open.
+    pub wanted: u32,
+    /// Cameras that are attached, as (index, name).
+    pub cameras: Vec<(u32, String)>,
+    /// Why the cameras couldn't be listed, if they couldn't.
+    pub problem: Option<String>,
+}
+
+/// Plays the test pattern in place of a camera that isn't there, following
+/// the device listing until the wanted camera shows up or one of the others
+/// is picked.
+pub struct IdleCard {
+    wanted: u32,
+    devices: watch::Receiver<Listing>,
+    failover: bool,
+    pattern: TestPattern,
+}
+
+impl IdleCard {
+    /// Waits for camera `wanted` on a listing from
+    /// [`super::hotplug::monitor`].
+    pub fn new(wanted: u32, devices: watch::Receiver<Listing>) -> Self {
+        let (width, height) = super::pattern::DEFAULT_SIZE;
+        Self {
+            wanted,
+            devices,
+            failover: false,
+            pattern: TestPattern::new(width, height, CARD_FPS),
+        }
+    }
+
+    /// Takes any attached camera when the wanted one isn't there, and only
+    /// shows the card when there is none at all.
+    pub fn failover(mut self, failover: bool) -> Self {
+        self.failover = failover;
+        self
+    }
+
+    /// Returns the camera to open: the wanted one as soon as it is attached,
+    /// or an attached one picked on `picks` meanwhile. Test card frames go
+    /// to `frames` and the card's contents to `status` until then, which is
+    /// cleared again before returning. `None` once nobody receives frames.
+    pub fn wait(
+        &mut self,
+        picks: &mut watch::Receiver<Option<u32>>,
+        status: &watch::Sender<Option<Waiting>>,
+        frames: &watch::Sender<Option<Frame>>,
+    ) -> Option<u32> {
+        // Only picks made while the card is up count.
+        picks.mark_unchanged();
+        let mut sequence = 0;
+
+        let chosen = loop {
+            let listing = self.devices.borrow_and_update().clone();
+            if is_attached(&listing, self.wanted) {
+                break Some(self.wanted);
+            }
+            if self.failover
+                && let Ok(&[(index, _), ..]) = listing.as_deref()
+            {
+                break Some(index);
+            }
+            if picks.has_changed().unwrap_or(false)
+                && let Some(index) = *picks.borrow_and_update()
+                && is_attached(&listing, index)
+            {
+                break Some(index);
+            }
+
+            let (cameras, problem) = match listing {
+                Ok(cameras) => (cameras, None),
+                Err(problem) => (Vec::new(), Some(problem)),
+            };
+            let waiting = Waiting {
+                wanted: self.wanted,
+                cameras,
+                problem,
+            };
+            status.send_if_modified(|shown| {
+                let changed = shown.as_ref() != Some(&waiting);
+                *shown = Some(waiting);
+                changed
+            });
+
+            let Ok(Some(mut frame)) = self.pattern.next_frame() else {
+                continue;
+            };
+            frame = Frame::new(frame.buffer, "idle".into(), ColorSpace::Srgb);
+            frame.sequence = sequence;
+            sequence += 1;
+            if frames.send(Some(frame)).is_err() {
+                break None;
+            }
+        };
+
+        status.send_replace(None);
+        chosen
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{thread, time::Duration};
+
+    use super::*;
+
+    fn camera(index: u32, name: &str) -> (u32, String) {
+        (index, name.to_string())
+    }
+
+    #[test]
+    fn wanted_camera_is_taken_as_soon_as_it_is_listed() {
+        let (devices_tx, devices) = watch::channel(Ok(Vec::new()));
+        let mut card = IdleCard::new(1, devices);
+        let (_pick_tx, mut picks) = watch::channel(None);
+        let (status, status_rx) = watch::channel(None);
+        let (frames, frames_rx) = watch::channel(None);
+
+        let plug = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            let _ = devices_tx.send_replace(Ok(vec![camera(1, "USB Camera")]));
+            devices_tx
+        });
+        assert_eq!(card.wait(&mut picks, &status, &frames), Some(1));
+        let _ = plug.join();
+        assert!(status_rx.borrow().is_none());
+        assert_eq!(frames_rx.borrow().as_ref().unwrap().source.as_ref(), "idle");
+    }
+
+    #[test]
+    fn another_attached_camera_can_be_picked() {
+        let (_devices_tx, devices) = watch::channel(Ok(vec![camera(2, "Capture")]));
+        let mut card = IdleCard::new(0, devices);
+        let (pick_tx, mut picks) = watch::channel(Some(2));
+        let (status, status_rx) = watch::channel(None);
+        let (frames, _frames_rx) = watch::channel(None);
+
+        let picker = thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            // Not attached, so ignored.
+            pick_tx.send_replace(Some(5));
+            thread::sleep(Duration::from_millis(100));
+            pick_tx.send_replace(Some(2));
+            pick_tx
+        });
+        assert_eq!(card.wait(&mut picks, &status, &frames), Some(2));
+        let _ = picker.join();
+        assert!(status_rx.borrow().is_none());
+    }
+
+    #[test]
+    fn failover_takes_any_attached_camera() {
+        let (_devices_tx, devices) = watch::channel(Ok(vec![camera(3, "Capture")]));
+        let mut card = IdleCard::new(0, devices).failover(true);
+        let (_pick_tx, mut picks) = watch::channel(None);
+        let (status, _status_rx) = watch::channel(None);
+        let (frames, _frames_rx) = watch::channel(None);
+
+        assert_eq!(card.wait(&mut picks, &status, &frames), Some(3));
+    }
+
+    #[test]
+    fn gives_up_without_receivers() {
+        let (_devices_tx, devices) = watch::channel(Err("denied".to_string()));
+        let mut card = IdleCard::new(0, devices);
+        let (_pick_tx, mut picks) = watch::channel(None);
+        let (status, _status_rx) = watch::channel(None);
+        let (frames, frames_rx) = watch::channel(None);
+        drop(frames_rx);
+
+        assert_eq!(card.wait(&mut picks, &status, &frames), None);
+    }
+}
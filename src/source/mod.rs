@@ -0,0 +1,138 @@
+//! Where frames come from.
+
+pub mod animation;
+pub mod camera;
+pub mod devices;
+pub mod hotplug;
+pub mod idle;
+pub mod network;
+pub mod pattern;
+pub mod pipe;
+#[cfg(feature = "screen")]
+pub mod screen;
+pub mod still;
+pub mod video;
+
+use std::{sync::Arc, time::Instant};
+
+use tokio::sync::watch;
+
+use crate::error::WebciiError;
+
+/// How the RGB values of a frame are to be interpreted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// The source didn't say; treated as sRGB.
+    #[default]
+    Unknown,
+    Srgb,
+}
+
+/// One RGB frame, ready for rendering, with where and when it came from.
+pub struct Frame {
+    pub buffer: image::ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    pub width: usize,
+    pub height: usize,
+    /// Raw RGB bytes of `buffer`, kept for comparing against the next frame.
+    pub pixels: Vec<u8>,
+    /// When the source produced the frame.
+    pub captured_at: Instant,
+    /// Position in the stream, counting from 0; assigned by [`pump`].
+    pub sequence: u64,
+    /// Identifies the source, e.g. `cam0` or a file name.
+    pub source: Arc<str>,
+    pub color_space: ColorSpace,
+}
+
+impl Frame {
+    /// Wraps a freshly captured image, stamped with the current time.
+    pub fn new(
+        buffer: image::ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+        source: Arc<str>,
+        color_space: ColorSpace,
+    ) -> Self {
+        let width = buffer.width() as usize;
+        let height = buffer.height() as usize;
+        let pixels = buffer.as_raw().to_vec();
+        Self {
+            buffer,
+            width,
+            height,
+            pixels,
+            captured_at: Instant::now(),
+            sequence: 0,
+            source,
+            color_space,
+        }
+    }
+}
+
+/// Anything that can produce a stream of frames.
+pub trait FrameSource {
+    /// Blocks until the next frame is available. `None` means the stream has
+    /// ended; live sources never end.
+    fn next_frame(&mut self) -> Result<Option<Frame>, WebciiError>;
+}
+
+/// Feeds frames from `source` into `frames` until the stream ends or every
+/// receiver is gone. Errors on single frames are reported and skipped.
+pub fn pump(source: &mut impl FrameSource, frames: &watch::Sender<Option<Frame>>) {
+    let mut sequence = 0;
+    loop {
+        match source.next_frame() {
+            Ok(Some(mut frame)) => {
+                frame.sequence = sequence;
+                sequence += 1;
+                if frames.send(Some(frame)).is_err() {
+                    break;
+                }
+            }
+            Ok(None) => break,
+            Err(e) => eprintln!("Frame error: {}", e),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::CameraError;
+
+    /// Yields `frames` frames, with an error in place of the second call.
+    struct Scripted {
+        calls: u32,
+        frames: u32,
+    }
+
+    impl FrameSource for Scripted {
+        fn next_frame(&mut self) -> Result<Option<Frame>, WebciiError> {
+            self.calls += 1;
+            if self.calls == 2 {
+                return Err(CameraError::PermissionDenied.into());
+            }
+            if self.frames == 0 {
+                return Ok(None);
+            }
+            self.frames -= 1;
+            let buffer = image::ImageBuffer::new(1, 1);
+            Ok(Some(Frame::new(buffer, "test".into(), ColorSpace::Srgb)))
+        }
+    }
+
+    #[test]
+    fn pump_numbers_frames_and_stops_at_end() {
+        let (tx, rx) = watch::channel(None);
+        pump(
+            &mut Scripted {
+                calls: 0,
+                frames: 3,
+            },
+            &tx,
+        );
+
+        let last = rx.borrow();
+        let last = last.as_ref().unwrap();
+        assert_eq!(last.sequence, 2);
+        assert_eq!(&*last.source, "test");
+    }
+}
@@ -0,0 +1,149 @@
+use std::{
+    thread,
+    time::{Duration, Instant},
+};
+
+use image::{Rgb, RgbImage};
+
+use super::{ColorSpace, Frame, FrameSource};
+use crate::error::WebciiError;
+
+pub const DEFAULT_SIZE: (u32, u32) = (640, 360);
+pub const DEFAULT_FPS: u32 = 30;
+
+/// 75% SMPTE color bars, left to right.
+const BARS: [[u8; 3]; 7] = [
+    [191, 191, 191],
+    [191, 191, 0],
+    [0, 191, 191],
+    [0, 191, 0],
+    [191, 0, 191],
+    [191, 0, 0],
+    [0, 0, 191],
+];
+/// The reversed strip under the bars, alternating with black.
+const CASTELLATIONS: [[u8; 3]; 7] = [
+    [0, 0, 191],
+    [16, 16, 16],
+    [191, 0, 191],
+    [16, 16, 16],
+    [0, 191, 191],
+    [16, 16, 16],
+    [191, 191, 191],
+];
+const BOX_COLOR: [u8; 3] = [255, 128, 0];
+/// How far the box moves per frame, in pixels on each axis.
+const BOX_SPEED: (u64, u64) = (5, 3);
+
+/// Synthetic frames for trying webcii without a camera: color bars over a
+/// castellation strip and a grey ramp, with a box bouncing across them.
+///
+/// Frame `n` depends on nothing but `n` and the size, so runs are
+/// reproducible for benchmarks and golden tests.
+pub struct TestPattern {
+    width: u32,
+    height: u32,
+    frame_interval: Duration,
+    frames: u64,
+    next_due: Option<Instant>,
+}
+
+impl TestPattern {
+    pub fn new(width: u32, height: u32, fps: u32) -> Self {
+        Self {
+            width: width.max(1),
+            height: height.max(1),
+            frame_interval: Duration::from_secs(1) / fps.max(1),
+            frames: 0,
+            next_due: None,
+        }
+    }
+
+    /// Frame number `n` of the pattern.
+    pub fn frame(&self, n: u64) -> RgbImage {
+        let (width, height) = (self.width, self.height);
+        let bars_end = height * 2 / 3;
+        let castellations_end = height * 3 / 4;
+        let mut image = RgbImage::from_fn(width, height, |x, y| {
+            let column = (x * 7 / width) as usize;
+            Rgb(if y < bars_end {
+                BARS[column]
+            } else if y < castellations_end {
+                CASTELLATIONS[column]
+            } else {
+                [(x * 255 / (width - 1).max(1)) as u8; 3]
+            })
+        });
+
+        let size = (height / 6).max(1).min(width);
+        let x = bounce(n * BOX_SPEED.0, (width - size) as u64) as u32;
+        let y = bounce(n * BOX_SPEED.1, (height - size) as u64) as u32;
+        for py in y..y + size {
+            for px in x..x + size {
+                image.put_pixel(px, py, Rgb(BOX_COLOR));
+            }
+        }
+        image
+    }
+}
+
+impl FrameSource for TestPattern {
+    fn next_frame(&mut self) -> Result<Option<Frame>, WebciiError> {
+        let now = Instant::now();
+        let due = self.next_due.unwrap_or(now);
+        if let Some(wait) = due.checked_duration_since(now) {
+            thread::sleep(wait);
+        }
+        self.next_due = Some(due + self.frame_interval);
+
+        let buffer = self.frame(self.frames);
+        self.frames += 1;
+        Ok(Some(Frame::new(buffer, "test".into(), ColorSpace::Srgb)))
+    }
+}
+
+/// Position after travelling `distance` back and forth along `span`.
+fn bounce(distance: u64, span: u64) -> u64 {
+    if span == 0 {
+        return 0;
+    }
+    let phase = distance % (2 * span);
+    if phase < span {
+        phase
+    } else {
+        2 * span - phase
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bars_ramp_and_box_are_where_expected() {
+        let pattern = TestPattern::new(70, 60, DEFAULT_FPS);
+        let frame = pattern.frame(0);
+        assert_eq!(frame.get_pixel(69, 39).0, BARS[6]);
+        assert_eq!(frame.get_pixel(5, 42).0, CASTELLATIONS[0]);
+        assert_eq!(frame.get_pixel(69, 59).0, [255; 3]);
+        // The box starts in the top-left corner and moves on.
+        assert_eq!(frame.get_pixel(0, 0).0, BOX_COLOR);
+        assert_ne!(pattern.frame(1).get_pixel(0, 0).0, BOX_COLOR);
+    }
+
+    #[test]
+    fn frames_are_reproducible() {
+        let a = TestPattern::new(64, 36, DEFAULT_FPS);
+        let b = TestPattern::new(64, 36, DEFAULT_FPS);
+        assert_eq!(a.frame(123), b.frame(123));
+    }
+
+    #[test]
+    fn box_bounces_off_the_edges() {
+        assert_eq!(bounce(0, 10), 0);
+        assert_eq!(bounce(10, 10), 10);
+        assert_eq!(bounce(13, 10), 7);
+        assert_eq!(bounce(20, 10), 0);
+        assert_eq!(bounce(7, 0), 0);
+    }
+}
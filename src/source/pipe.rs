@@ -0,0 +1,258 @@
+use std::{
+    io::{self, BufRead, BufReader, Read},
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{ColorSpace, Frame, FrameSource};
+use crate::error::{PipeError, WebciiError};
+
+/// How the chroma planes of a Y4M stream are subsampled, as log2 of the
+/// horizontal and vertical factors; `None` for greyscale.
+type Chroma = Option<(u32, u32)>;
+
+enum Format {
+    Rgb24,
+    Y4m(Chroma),
+}
+
+/// Frames piped in on a reader, usually stdin: either a YUV4MPEG2 stream
+/// (`ffmpeg -f yuv4mpegpipe -`) or headerless packed RGB24 of a known size.
+pub struct PipeSource<R> {
+    reader: BufReader<R>,
+    format: Format,
+    width: u32,
+    height: u32,
+    /// `None` hands frames out as fast as they arrive.
+    frame_interval: Option<Duration>,
+    started: Option<Instant>,
+    frames_read: u32,
+}
+
+impl<R: Read> PipeSource<R> {
+    /// Reads the stream header; size, frame rate and chroma layout come from
+    /// it.
+    pub fn y4m(reader: R) -> Result<Self, PipeError> {
+        let mut reader = BufReader::new(reader);
+        let mut line = Vec::new();
+        reader
+            .read_until(b'\n', &mut line)
+            .map_err(PipeError::Read)?;
+        let header = String::from_utf8_lossy(&line);
+        let (width, height, fps, chroma) = parse_y4m_header(header.trim_end())?;
+
+        Ok(Self {
+            reader,
+            format: Format::Y4m(chroma),
+            width,
+            height,
+            frame_interval: fps.map(|fps| Duration::from_secs_f64(1.0 / fps)),
+            started: None,
+            frames_read: 0,
+        })
+    }
+
+    /// Headerless `rgb24` frames of `width` x `height`, played at `fps` when
+    /// given.
+    pub fn raw(reader: R, width: u32, height: u32, fps: Option<u32>) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            format: Format::Rgb24,
+            width,
+            height,
+            frame_interval: fps
+                .filter(|&fps| fps > 0)
+                .map(|fps| Duration::from_secs(1) / fps),
+            started: None,
+            frames_read: 0,
+        }
+    }
+
+    /// Fills `buffer` from the stream; `false` at a clean end of stream.
+    fn read_exact_or_end(&mut self, buffer: &mut [u8]) -> Result<bool, PipeError> {
+        match self.reader.read_exact(buffer) {
+            Ok(()) => Ok(true),
+            // A truncated last frame is as good as the end.
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+            Err(e) => Err(PipeError::Read(e)),
+        }
+    }
+
+    fn read_frame(&mut self) -> Result<Option<Vec<u8>>, PipeError> {
+        let (width, height) = (self.width as usize, self.height as usize);
+        match self.format {
+            Format::Rgb24 => {
+                let mut pixels = vec![0; width * height * 3];
+                Ok(self.read_exact_or_end(&mut pixels)?.then_some(pixels))
+            }
+            Format::Y4m(chroma) => {
+                let mut line = Vec::new();
+                self.reader
+                    .read_until(b'\n', &mut line)
+                    .map_err(PipeError::Read)?;
+                if line.is_empty() {
+                    return Ok(None);
+                }
+                if !line.starts_with(b"FRAME") {
+                    return Err(PipeError::Header(
+                        "expected a FRAME marker between frames".to_string(),
+                    ));
+                }
+
+                let (chroma_width, chroma_height) = match chroma {
+                    Some((sx, sy)) => (width.div_ceil(1 << sx), height.div_ceil(1 << sy)),
+                    None => (0, 0),
+                };
+                let mut planes = vec![0; width * height + 2 * chroma_width * chroma_height];
+                if !self.read_exact_or_end(&mut planes)? {
+                    return Ok(None);
+                }
+                Ok(Some(yuv_to_rgb(&planes, width, height, chroma)))
+            }
+        }
+    }
+}
+
+impl<R: Read> FrameSource for PipeSource<R> {
+    fn next_frame(&mut self) -> Result<Option<Frame>, WebciiError> {
+        let Some(pixels) = self.read_frame()? else {
+            return Ok(None);
+        };
+
+        if let Some(interval) = self.frame_interval {
+            let started = *self.started.get_or_insert_with(Instant::now);
+            let due = started + interval * self.frames_read;
+            if let Some(wait) = due.checked_duration_since(Instant::now()) {
+                thread::sleep(wait);
+            }
+        }
+        self.frames_read += 1;
+
+        let buffer = image::ImageBuffer::from_raw(self.width, self.height, pixels)
+            .expect("buffer holds exactly one frame");
+        Ok(Some(Frame::new(buffer, "stdin".into(), ColorSpace::Srgb)))
+    }
+}
+
+/// Width, height, frame rate and chroma layout from a `YUV4MPEG2` stream
+/// header line.
+fn parse_y4m_header(header: &str) -> Result<(u32, u32, Option<f64>, Chroma), PipeError> {
+    let mut fields = header.split(' ');
+    if fields.next() != Some("YUV4MPEG2") {
+        return Err(PipeError::Header(
+            "stdin doesn't start with a YUV4MPEG2 header".to_string(),
+        ));
+    }
+
+    let (mut width, mut height, mut fps) = (None, None, None);
+    let mut chroma = Some((1, 1));
+    for field in fields {
+        let Some(tag) = field.chars().next() else {
+            continue;
+        };
+        let value = &field[tag.len_utf8()..];
+        match tag {
+            'W' => width = value.parse().ok(),
+            'H' => height = value.parse().ok(),
+            'F' => {
+                fps = value.split_once(':').and_then(|(num, den)| {
+                    let rate = num.parse::<f64>().ok()? / den.parse::<f64>().ok()?;
+                    (rate.is_finite() && rate > 0.0).then_some(rate)
+                })
+            }
+            'C' => {
+                chroma = match value {
+                    "420" | "420jpeg" | "420paldv" | "420mpeg2" => Some((1, 1)),
+                    "422" => Some((1, 0)),
+                    "444" => Some((0, 0)),
+                    "mono" => None,
+                    _ => return Err(PipeError::Unsupported(format!("colorspace C{}", value))),
+                }
+            }
+            _ => {}
+        }
+    }
+
+    match (width, height) {
+        (Some(width), Some(height)) if width > 0 && height > 0 => Ok((width, height, fps, chroma)),
+        _ => Err(PipeError::Header(
+            "YUV4MPEG2 header has no frame size".to_string(),
+        )),
+    }
+}
+
+/// Planar 8-bit YUV (Y, then U, then V) to packed RGB, with the BT.601
+/// limited-range matrix ffmpeg uses for standard-definition Y4M output.
+fn yuv_to_rgb(planes: &[u8], width: usize, height: usize, chroma: Chroma) -> Vec<u8> {
+    let (luma, rest) = planes.split_at(width * height);
+    let (sx, sy) = chroma.unwrap_or((0, 0));
+    let chroma_width = width.div_ceil(1 << sx);
+    let (u_plane, v_plane) = rest.split_at(rest.len() / 2);
+
+    let mut rgb = Vec::with_capacity(width * height * 3);
+    for y in 0..height {
+        for x in 0..width {
+            let c = (luma[y * width + x] as i32 - 16) * 298;
+            let (d, e) = match chroma {
+                Some(_) => {
+                    let idx = (y >> sy) * chroma_width + (x >> sx);
+                    (u_plane[idx] as i32 - 128, v_plane[idx] as i32 - 128)
+                }
+                None => (0, 0),
+            };
+            rgb.extend(
+                [c + 409 * e, c - 100 * d - 208 * e, c + 516 * d]
+                    .map(|v| ((v + 128) >> 8).clamp(0, 255) as u8),
+            );
+        }
+    }
+    rgb
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Cursor;
+
+    use super::*;
+
+    #[test]
+    fn y4m_header_is_parsed() {
+        let (width, height, fps, chroma) =
+            parse_y4m_header("YUV4MPEG2 W640 H480 F30000:1001 Ip A1:1 C420jpeg XYSCSS=420JPEG")
+                .unwrap();
+        assert_eq!((width, height, chroma), (640, 480, Some((1, 1))));
+        assert!((fps.unwrap() - 29.97).abs() < 0.01);
+
+        assert!(parse_y4m_header("P6 640 480").is_err());
+        assert!(matches!(
+            parse_y4m_header("YUV4MPEG2 W2 H2 C420p10"),
+            Err(PipeError::Unsupported(_))
+        ));
+    }
+
+    #[test]
+    fn y4m_frames_are_converted_until_end_of_stream() {
+        // 2x2 4:2:0, one white frame then one black frame, no frame rate.
+        let mut stream = b"YUV4MPEG2 W2 H2 C420\n".to_vec();
+        for luma in [235, 16] {
+            stream.extend(b"FRAME\n");
+            stream.extend([luma; 4]);
+            stream.extend([128, 128]);
+        }
+
+        let mut pipe = PipeSource::y4m(Cursor::new(stream)).unwrap();
+        assert_eq!(pipe.next_frame().unwrap().unwrap().pixels, [255; 12]);
+        assert_eq!(pipe.next_frame().unwrap().unwrap().pixels, [0; 12]);
+        assert!(pipe.next_frame().unwrap().is_none());
+    }
+
+    #[test]
+    fn raw_frames_pass_through() {
+        let stream: Vec<u8> = (0..12).collect();
+        let mut pipe = PipeSource::raw(Cursor::new(stream), 2, 2, None);
+        let frame = pipe.next_frame().unwrap().unwrap();
+        assert_eq!(frame.pixels, (0..12).collect::<Vec<u8>>());
+        assert_eq!(&*frame.source, "stdin");
+        assert!(pipe.next_frame().unwrap().is_none());
+    }
+}
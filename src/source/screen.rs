@@ -0,0 +1,59 @@
+use std::{
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use xcap::Monitor;
+
+use super::{ColorSpace, Frame, FrameSource};
+use crate::error::{ScreenError, WebciiError};
+
+/// Time between screenshots. Grabbing a whole display is far slower than
+/// reading a camera, so this doesn't try to keep up with the monitor.
+const CAPTURE_INTERVAL: Duration = Duration::from_millis(66);
+
+/// One display, captured as a series of screenshots.
+pub struct ScreenSource {
+    monitor: Monitor,
+    index: usize,
+    id: Arc<str>,
+    last_captured: Option<Instant>,
+}
+
+impl ScreenSource {
+    /// Opens display `index`, counting from 0 in the order the platform
+    /// lists them.
+    pub fn open(index: usize) -> Result<Self, ScreenError> {
+        let mut monitors = Monitor::all().map_err(|e| ScreenError::Query(e.to_string()))?;
+        let count = monitors.len();
+        if index >= count {
+            return Err(ScreenError::NoDisplay { index, count });
+        }
+        Ok(Self {
+            monitor: monitors.swap_remove(index),
+            index,
+            id: format!("screen{}", index).into(),
+            last_captured: None,
+        })
+    }
+}
+
+impl FrameSource for ScreenSource {
+    fn next_frame(&mut self) -> Result<Option<Frame>, WebciiError> {
+        if let Some(captured) = self.last_captured {
+            thread::sleep(CAPTURE_INTERVAL.saturating_sub(captured.elapsed()));
+        }
+        self.last_captured = Some(Instant::now());
+
+        let image = self
+            .monitor
+            .capture_image()
+            .map_err(|e| ScreenError::Capture {
+                index: self.index,
+                message: e.to_string(),
+            })?;
+        let buffer = image::DynamicImage::from(image).into_rgb8();
+        Ok(Some(Frame::new(buffer, self.id.clone(), ColorSpace::Srgb)))
+    }
+}
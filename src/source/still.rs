@@ -0,0 +1,54 @@
+use std::{
+    path::Path,
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{ColorSpace, Frame, FrameSource};
+use crate::error::{ImageError, WebciiError};
+
+/// How often a still image is handed out again, so resizes and toggled
+/// settings show up without waiting on a source that never changes.
+const REFRESH_INTERVAL: Duration = Duration::from_millis(100);
+
+/// A single picture, loaded with the `image` crate and repeated for as long
+/// as it is read.
+pub struct ImageSource {
+    buffer: image::ImageBuffer<image::Rgb<u8>, Vec<u8>>,
+    id: Arc<str>,
+    last_shown: Option<Instant>,
+}
+
+impl ImageSource {
+    pub fn open(path: &Path) -> Result<Self, ImageError> {
+        let image = image::open(path).map_err(|source| ImageError::Load {
+            path: path.to_path_buf(),
+            source,
+        })?;
+        Ok(Self {
+            buffer: image.into_rgb8(),
+            id: path
+                .file_name()
+                .unwrap_or(path.as_os_str())
+                .to_string_lossy()
+                .into(),
+            last_shown: None,
+        })
+    }
+
+    /// The image as a frame, without the repeat pacing.
+    pub fn frame(&self) -> Frame {
+        Frame::new(self.buffer.clone(), self.id.clone(), ColorSpace::Srgb)
+    }
+}
+
+impl FrameSource for ImageSource {
+    fn next_frame(&mut self) -> Result<Option<Frame>, WebciiError> {
+        if let Some(shown) = self.last_shown {
+            thread::sleep(REFRESH_INTERVAL.saturating_sub(shown.elapsed()));
+        }
+        self.last_shown = Some(Instant::now());
+        Ok(Some(self.frame()))
+    }
+}
@@ -0,0 +1,195 @@
+use std::{
+    ffi::OsStr,
+    io::{self, Read},
+    path::Path,
+    process::{Child, ChildStdout, Command, Stdio},
+    sync::Arc,
+    thread,
+    time::{Duration, Instant},
+};
+
+use super::{ColorSpace, Frame, FrameSource};
+use crate::error::{VideoError, WebciiError};
+
+/// A video decoded by an `ffmpeg` child process into raw RGB frames: a local
+/// file paced at its native frame rate, or a live stream shown as it
+/// arrives.
+pub struct VideoSource {
+    id: Arc<str>,
+    child: Child,
+    stdout: ChildStdout,
+    width: u32,
+    height: u32,
+    /// `None` for live streams, which pace themselves.
+    frame_interval: Option<Duration>,
+    started: Option<Instant>,
+    frames_read: u32,
+    finished: bool,
+}
+
+impl VideoSource {
+    pub fn open(path: &Path) -> Result<Self, VideoError> {
+        let id = path
+            .file_name()
+            .unwrap_or(path.as_os_str())
+            .to_string_lossy();
+        Self::spawn(path.as_os_str(), &[], path, id.into(), true)
+    }
+
+    /// Connects to a live `url` that ffmpeg can read, such as RTSP or
+    /// MJPEG over HTTP. `label` stands in for the URL in errors and `id` in
+    /// the title bar, so neither has to carry credentials.
+    pub fn open_stream(url: &str, label: &str, id: Arc<str>) -> Result<Self, VideoError> {
+        let mut input_args = vec!["-fflags", "nobuffer"];
+        if url.starts_with("rtsp") {
+            // UDP drops packets on anything but a quiet LAN.
+            input_args.extend(["-rtsp_transport", "tcp"]);
+        }
+        Self::spawn(OsStr::new(url), &input_args, Path::new(label), id, false)
+    }
+
+    fn spawn(
+        input: &OsStr,
+        input_args: &[&str],
+        label: &Path,
+        id: Arc<str>,
+        paced: bool,
+    ) -> Result<Self, VideoError> {
+        let probe_error = |message: String| VideoError::Probe {
+            path: label.to_path_buf(),
+            message,
+        };
+        let probe = Command::new("ffprobe")
+            .args(["-v", "error", "-select_streams", "v:0"])
+            .args(["-show_entries", "stream=width,height,r_frame_rate"])
+            .args(["-of", "default=noprint_wrappers=1"])
+            .args(input_args)
+            .arg(input)
+            .output()
+            .map_err(|e| VideoError::Spawn("ffprobe", e))?;
+        if !probe.status.success() {
+            return Err(probe_error(
+                String::from_utf8_lossy(&probe.stderr).trim().to_string(),
+            ));
+        }
+        let (width, height, fps) = parse_probe(&String::from_utf8_lossy(&probe.stdout))
+            .ok_or_else(|| {
+                probe_error("no video stream with a known size and frame rate".to_string())
+            })?;
+
+        let mut child = Command::new("ffmpeg")
+            .args(["-v", "error", "-nostdin"])
+            .args(input_args)
+            .arg("-i")
+            .arg(input)
+            .args(["-map", "0:v:0", "-f", "rawvideo", "-pix_fmt", "rgb24", "-"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()
+            .map_err(|e| VideoError::Spawn("ffmpeg", e))?;
+        let stdout = child.stdout.take().expect("stdout is piped");
+
+        Ok(Self {
+            id,
+            child,
+            stdout,
+            width,
+            height,
+            frame_interval: paced.then(|| Duration::from_secs_f64(1.0 / fps)),
+            started: None,
+            frames_read: 0,
+            finished: false,
+        })
+    }
+}
+
+impl FrameSource for VideoSource {
+    fn next_frame(&mut self) -> Result<Option<Frame>, WebciiError> {
+        if self.finished {
+            return Ok(None);
+        }
+
+        let mut pixels = vec![0; self.width as usize * self.height as usize * 3];
+        match self.stdout.read_exact(&mut pixels) {
+            Ok(()) => {}
+            Err(e) => {
+                self.finished = true;
+                return match e.kind() {
+                    io::ErrorKind::UnexpectedEof => Ok(None),
+                    _ => Err(VideoError::Read(e).into()),
+                };
+            }
+        }
+
+        // Hold each frame back until its presentation time, measured from
+        // when the first one was shown.
+        if let Some(interval) = self.frame_interval {
+            let started = *self.started.get_or_insert_with(Instant::now);
+            let due = started + interval * self.frames_read;
+            if let Some(wait) = due.checked_duration_since(Instant::now()) {
+                thread::sleep(wait);
+            }
+        }
+        self.frames_read += 1;
+
+        let buffer = image::ImageBuffer::from_raw(self.width, self.height, pixels)
+            .expect("buffer holds exactly one frame");
+        // ffmpeg converts to rgb24 from whatever matrix the file declares.
+        Ok(Some(Frame::new(buffer, self.id.clone(), ColorSpace::Srgb)))
+    }
+}
+
+impl Drop for VideoSource {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Width, height and frame rate from `ffprobe`'s `key=value` output.
+fn parse_probe(output: &str) -> Option<(u32, u32, f64)> {
+    let (mut width, mut height, mut fps) = (None, None, None);
+    for line in output.lines() {
+        match line.trim().split_once('=') {
+            Some(("width", value)) => width = value.parse().ok(),
+            Some(("height", value)) => height = value.parse().ok(),
+            Some(("r_frame_rate", value)) => fps = parse_rate(value),
+            _ => {}
+        }
+    }
+    let (width, height, fps) = (width?, height?, fps?);
+    (width > 0 && height > 0).then_some((width, height, fps))
+}
+
+/// Parses a rational rate such as `30000/1001`, or a plain number.
+fn parse_rate(value: &str) -> Option<f64> {
+    let rate = match value.split_once('/') {
+        Some((num, den)) => num.parse::<f64>().ok()? / den.parse::<f64>().ok()?,
+        None => value.parse().ok()?,
+    };
+    (rate.is_finite() && rate > 0.0).then_some(rate)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn probe_output_is_parsed() {
+        let output = "width=1280\nheight=720\nr_frame_rate=30000/1001\n";
+        let (width, height, fps) = parse_probe(output).unwrap();
+        assert_eq!((width, height), (1280, 720));
+        assert!((fps - 29.97).abs() < 0.01);
+    }
+
+    #[test]
+    fn unknown_rate_is_rejected() {
+        assert_eq!(parse_rate("0/0"), None);
+        assert_eq!(parse_rate("25"), Some(25.0));
+        assert_eq!(
+            parse_probe("width=1280\nheight=720\nr_frame_rate=0/0\n"),
+            None
+        );
+    }
+}
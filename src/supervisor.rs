@@ -0,0 +1,262 @@
+//! Restarts background tasks that fail or panic, and reports how they are
+//! doing so the title bar can show it.
+
+use std::{collections::BTreeMap, error::Error, fmt, time::Duration};
+
+use tokio::{sync::watch, task::JoinHandle, time::Instant};
+
+const INITIAL_BACKOFF: Duration = Duration::from_millis(250);
+const MAX_BACKOFF: Duration = Duration::from_secs(5);
+/// A run this long counts as healthy: the failure after it starts the
+/// count and the backoff over, so failures hours apart never add up to
+/// giving up.
+const HEALTHY_RUN: Duration = MAX_BACKOFF.saturating_mul(6);
+
+type BoxError = Box<dyn Error + Send + Sync>;
+
+/// What to do when a task ends with an error or a panic. A task that
+/// returns `Ok` is done and never restarted.
+#[derive(Clone, Copy, Debug)]
+pub enum Restart {
+    Never,
+    /// Start it again after a growing delay, at most this many times in a
+    /// row: a run that lasts [`HEALTHY_RUN`] resets the count.
+    OnFailure {
+        max_restarts: u32,
+    },
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TaskStatus {
+    Running,
+    /// Failed `attempt` times so far and waiting to start again.
+    Restarting {
+        attempt: u32,
+        max_restarts: u32,
+    },
+    Finished,
+    Failed,
+}
+
+/// A task that failed and ran out of restarts.
+#[derive(Debug)]
+pub struct TaskError {
+    pub task: &'static str,
+    pub restarts: u32,
+    cause: BoxError,
+}
+
+impl fmt::Display for TaskError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.cause)?;
+        if self.restarts > 0 {
+            let plural = if self.restarts == 1 { "" } else { "s" };
+            write!(
+                f,
+                " (the {} task gave up after {} restart{})",
+                self.task, self.restarts, plural
+            )?;
+        }
+        Ok(())
+    }
+}
+
+impl Error for TaskError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(self.cause.as_ref())
+    }
+}
+
+/// Runs tasks under restart policies, publishing each task's status.
+#[derive(Clone)]
+pub struct Supervisor {
+    statuses: watch::Sender<BTreeMap<&'static str, TaskStatus>>,
+}
+
+impl Supervisor {
+    pub fn new() -> (Self, watch::Receiver<BTreeMap<&'static str, TaskStatus>>) {
+        let (statuses, rx) = watch::channel(BTreeMap::new());
+        (Self { statuses }, rx)
+    }
+
+    /// Calls `start` to spawn the task (with `tokio::spawn` or
+    /// `spawn_blocking`) and again after every failure the policy allows.
+    /// Resolves when the task finishes or gives up.
+    pub async fn run<E, F>(
+        &self,
+        name: &'static str,
+        policy: Restart,
+        mut start: F,
+    ) -> Result<(), TaskError>
+    where
+        E: Into<BoxError>,
+        F: FnMut() -> JoinHandle<Result<(), E>>,
+    {
+        let mut failures = 0;
+        loop {
+            self.set(name, TaskStatus::Running);
+            let started = Instant::now();
+            let cause: BoxError = match start().await {
+                Ok(Ok(())) => {
+                    self.set(name, TaskStatus::Finished);
+                    return Ok(());
+                }
+                Ok(Err(e)) => e.into(),
+                Err(e) if e.is_panic() => format!("the {} task panicked", name).into(),
+                Err(e) => e.into(),
+            };
+
+            if started.elapsed() >= HEALTHY_RUN {
+                failures = 0;
+            }
+            match policy {
+                Restart::OnFailure { max_restarts } if failures < max_restarts => {
+                    failures += 1;
+                    self.set(
+                        name,
+                        TaskStatus::Restarting {
+                            attempt: failures,
+                            max_restarts,
+                        },
+                    );
+                    tokio::time::sleep(backoff(failures)).await;
+                }
+                _ => {
+                    self.set(name, TaskStatus::Failed);
+                    return Err(TaskError {
+                        task: name,
+                        restarts: failures,
+                        cause,
+                    });
+                }
+            }
+        }
+    }
+
+    fn set(&self, name: &'static str, status: TaskStatus) {
+        self.statuses.send_modify(|statuses| {
+            statuses.insert(name, status);
+        });
+    }
+}
+
+/// Delay before restart number `attempt` (from 1): doubling from
+/// [`INITIAL_BACKOFF`] up to [`MAX_BACKOFF`].
+fn backoff(attempt: u32) -> Duration {
+    INITIAL_BACKOFF
+        .saturating_mul(1 << (attempt - 1).min(16))
+        .min(MAX_BACKOFF)
+}
+
+/// Short description of the tasks that aren't running normally, for the
+/// title bar; `None` when all is well.
+pub fn summary(statuses: &BTreeMap<&'static str, TaskStatus>) -> Option<String> {
+    let problems: Vec<String> = statuses
+        .iter()
+        .filter_map(|(name, status)| match status {
+            TaskStatus::Restarting {
+                attempt,
+                max_restarts,
+            } => Some(format!("{} restarting {}/{}", name, attempt, max_restarts)),
+            TaskStatus::Failed => Some(format!("{} failed", name)),
+            TaskStatus::Running | TaskStatus::Finished => None,
+        })
+        .collect();
+    (!problems.is_empty()).then(|| problems.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{
+        Arc,
+        atomic::{AtomicU32, Ordering},
+    };
+
+    use super::*;
+
+    #[test]
+    fn backoff_doubles_up_to_cap() {
+        assert_eq!(backoff(1), INITIAL_BACKOFF);
+        assert_eq!(backoff(2), INITIAL_BACKOFF * 2);
+        assert_eq!(backoff(40), MAX_BACKOFF);
+    }
+
+    #[tokio::test]
+    async fn failing_task_is_restarted_then_gives_up() {
+        let (supervisor, statuses) = Supervisor::new();
+        let starts = Arc::new(AtomicU32::new(0));
+
+        let counter = starts.clone();
+        let result = supervisor
+            .run(
+                "capture",
+                Restart::OnFailure { max_restarts: 1 },
+                move || {
+                    counter.fetch_add(1, Ordering::SeqCst);
+                    tokio::spawn(async { Err::<(), _>("camera unplugged") })
+                },
+            )
+            .await;
+
+        assert_eq!(starts.load(Ordering::SeqCst), 2);
+        let error = result.unwrap_err();
+        assert_eq!(error.restarts, 1);
+        assert_eq!(
+            error.to_string(),
+            "camera unplugged (the capture task gave up after 1 restart)"
+        );
+        assert_eq!(
+            summary(&statuses.borrow()).as_deref(),
+            Some("capture failed")
+        );
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn failures_after_healthy_runs_keep_being_retried() {
+        let (supervisor, _statuses) = Supervisor::new();
+        let starts = Arc::new(AtomicU32::new(0));
+
+        let counter = starts.clone();
+        supervisor
+            .run(
+                "capture",
+                Restart::OnFailure { max_restarts: 1 },
+                move || {
+                    let start = counter.fetch_add(1, Ordering::SeqCst);
+                    tokio::spawn(async move {
+                        if start == 5 {
+                            return Ok(());
+                        }
+                        tokio::time::sleep(HEALTHY_RUN).await;
+                        Err("stream dropped")
+                    })
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(starts.load(Ordering::SeqCst), 6);
+    }
+
+    #[tokio::test]
+    async fn panics_count_as_failures() {
+        let (supervisor, statuses) = Supervisor::new();
+        let mut first = true;
+
+        supervisor
+            .run("input", Restart::OnFailure { max_restarts: 1 }, move || {
+                let panic = std::mem::replace(&mut first, false);
+                tokio::spawn(async move {
+                    if panic {
+                        panic!("boom");
+                    }
+                    Ok::<(), BoxError>(())
+                })
+            })
+            .await
+            .unwrap();
+
+        assert_eq!(statuses.borrow()["input"], TaskStatus::Finished);
+        assert_eq!(summary(&statuses.borrow()), None);
+    }
+}
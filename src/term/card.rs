@@ -0,0 +1,101 @@
+//! The box drawn over the test card while webcii waits for a camera.
+
+use std::io::{self, Write};
+
+use crossterm::{cursor, queue};
+
+use crate::source::idle::Waiting;
+
+/// What the card says: which camera is missing, what to try, and the other
+/// cameras that can be picked instead.
+pub fn lines(waiting: &Waiting) -> Vec<String> {
+    let mut lines = vec![
+        format!("Waiting for camera {}", waiting.wanted),
+        "webcii starts as soon as it is plugged in.".to_string(),
+        String::new(),
+    ];
+    match &waiting.problem {
+        Some(problem) => lines.push(problem.clone()),
+        None => lines.push("Check that it is connected and not in use elsewhere.".to_string()),
+    }
+    lines.push("`webcii --source test` runs without a camera.".to_string());
+
+    let others: Vec<_> = waiting
+        .cameras
+        .iter()
+        .filter(|&&(index, _)| index != waiting.wanted && index < 10)
+        .collect();
+    if !others.is_empty() {
+        lines.push(String::new());
+        lines.push("Press a number to use another camera:".to_string());
+        for (index, name) in others {
+            lines.push(format!("  {}  {}", index, name));
+        }
+    }
+    lines.push(String::new());
+    lines.push("q quits".to_string());
+    lines
+}
+
+/// Draws `lines` in a box centered on a `terminal` of (columns, rows),
+/// cutting lines that don't fit. `plain` leaves the colors out.
+pub fn draw<W: Write>(
+    out: &mut W,
+    lines: &[String],
+    terminal: (usize, usize),
+    plain: bool,
+) -> io::Result<()> {
+    let (term_width, term_height) = terminal;
+    let inner = lines
+        .iter()
+        .map(|line| line.chars().count())
+        .max()
+        .unwrap_or(0)
+        .min(term_width.saturating_sub(2));
+    let (width, height) = (inner + 2, (lines.len() + 2).min(term_height));
+    let x = (term_width.saturating_sub(width) / 2) as u16;
+    let y = (term_height.saturating_sub(height) / 2) as u16;
+
+    let blank = String::new();
+    let padded = std::iter::once(&blank)
+        .chain(lines)
+        .chain([&blank])
+        .take(height);
+    for (i, line) in padded.enumerate() {
+        let text: String = line.chars().take(inner).collect();
+        queue!(out, cursor::MoveTo(x, y + i as u16))?;
+        if plain {
+            write!(out, " {:<inner$} ", text)?;
+        } else {
+            write!(out, "\x1b[0;30;47m {:<inner$} \x1b[0m", text)?;
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn card_offers_the_other_cameras() {
+        let waiting = Waiting {
+            wanted: 0,
+            cameras: vec![(1, "USB Camera".to_string()), (12, "Far".to_string())],
+            problem: None,
+        };
+        let lines = lines(&waiting);
+        assert_eq!(lines[0], "Waiting for camera 0");
+        assert!(lines.contains(&"  1  USB Camera".to_string()));
+        assert!(!lines.iter().any(|line| line.contains("Far")));
+
+        let mut out = Vec::new();
+        draw(&mut out, &lines[..1], (30, 5), true).unwrap();
+        assert_eq!(
+            String::from_utf8(out).unwrap(),
+            "\x1b[2;5H                      \
+             \x1b[3;5H Waiting for camera 0 \
+             \x1b[4;5H                      "
+        );
+    }
+}
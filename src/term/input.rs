@@ -0,0 +1,87 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use tokio::sync::watch;
+
+use crate::{
+    effects::overlay,
+    render::{preset, settings::RenderSettings, tone},
+};
+
+/// Applies one key press to the live settings. Returns `true` when the key
+/// asks to quit.
+pub fn handle_key(key: KeyEvent, settings: &watch::Sender<RenderSettings>) -> bool {
+    match key.code {
+        KeyCode::Char('q') => return true,
+        KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => return true,
+        KeyCode::Char('c') => {
+            settings.send_modify(|s| s.colorizer = s.colorizer.next());
+        }
+        KeyCode::Char('m') => {
+            settings.send_modify(|s| s.mode = s.mode.next());
+        }
+        KeyCode::Char('v') => {
+            settings.send_modify(|s| s.view = s.view.next());
+        }
+        KeyCode::Char('z') => {
+            settings.send_modify(|s| s.zebra = !s.zebra);
+        }
+        KeyCode::Char('b') => {
+            settings.send_modify(|s| s.blobs = !s.blobs);
+        }
+        KeyCode::Char('t') => {
+            settings.send_modify(|s| s.tracking = !s.tracking);
+        }
+        KeyCode::Char('h') => {
+            settings.send_modify(|s| s.heatmap = !s.heatmap);
+        }
+        KeyCode::Char('s') => {
+            settings.send_modify(|s| s.hud = !s.hud);
+        }
+        KeyCode::Char('g') => {
+            settings
+                .send_modify(|s| s.charset = preset::next(s.charset.as_deref()).map(String::from));
+        }
+        KeyCode::Char('a') => {
+            settings.send_modify(|s| s.adaptive_charset = !s.adaptive_charset);
+        }
+        KeyCode::Char('f') => {
+            settings.send_modify(|s| s.peaking = !s.peaking);
+        }
+        KeyCode::Char('F') => {
+            settings.send_modify(|s| {
+                s.peaking_color = (s.peaking_color + 1) % overlay::PEAKING_COLORS.len()
+            });
+        }
+        // Lower threshold = more sensitive, so '+' steps it down.
+        KeyCode::Char('+') | KeyCode::Char('=') => {
+            settings.send_modify(|s| {
+                s.peaking_threshold = (s.peaking_threshold - overlay::PEAKING_THRESHOLD_STEP)
+                    .max(overlay::PEAKING_THRESHOLD_STEP)
+            });
+        }
+        KeyCode::Char('-') => {
+            settings.send_modify(|s| s.peaking_threshold += overlay::PEAKING_THRESHOLD_STEP);
+        }
+        KeyCode::Char('[') => {
+            settings.send_modify(|s| s.brightness = tone::step_brightness(s.brightness, -1));
+        }
+        KeyCode::Char(']') => {
+            settings.send_modify(|s| s.brightness = tone::step_brightness(s.brightness, 1));
+        }
+        KeyCode::Char('{') => {
+            settings.send_modify(|s| s.contrast = tone::step_contrast(s.contrast, -1));
+        }
+        KeyCode::Char('}') => {
+            settings.send_modify(|s| s.contrast = tone::step_contrast(s.contrast, 1));
+        }
+        _ => {}
+    }
+    false
+}
+
+/// The camera a digit key picks on the idle card.
+pub fn camera_choice(key: KeyEvent) -> Option<u32> {
+    match key.code {
+        KeyCode::Char(c) => c.to_digit(10),
+        _ => None,
+    }
+}
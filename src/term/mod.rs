@@ -0,0 +1,70 @@
+//! Terminal setup and teardown, the title bar and key handling.
+
+pub mod card;
+pub mod input;
+pub mod query;
+
+use std::{io, time::Duration};
+
+use crossterm::{cursor, execute, style, terminal};
+
+use crate::error::TerminalError;
+
+pub const TITLE_REFRESH_INTERVAL: Duration = Duration::from_secs(1);
+/// How long a notice, such as a camera being plugged in, stays in the title.
+pub const NOTICE_DURATION: Duration = Duration::from_secs(5);
+
+/// How long the key reader waits for an event before checking whether it
+/// is still wanted.
+pub const INPUT_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Raw mode with a hidden cursor for as long as the guard lives; dropping it
+/// restores the terminal, including on early returns and panics.
+pub struct TerminalGuard;
+
+impl TerminalGuard {
+    /// Enables raw mode, clears the screen and hides the cursor.
+    pub fn enter() -> Result<Self, TerminalError> {
+        terminal::enable_raw_mode().map_err(TerminalError::Setup)?;
+        let guard = TerminalGuard;
+        execute!(
+            io::stdout(),
+            terminal::Clear(terminal::ClearType::All),
+            cursor::Hide
+        )
+        .map_err(TerminalError::Setup)?;
+        Ok(guard)
+    }
+}
+
+/// Terminal size in cells, as (columns, rows).
+pub fn size() -> Result<(usize, usize), TerminalError> {
+    let (cols, rows) = terminal::size().map_err(TerminalError::Size)?;
+    Ok((cols as usize, rows as usize))
+}
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        // Cropped rows can end without the reset the renderer put after
+        // the last cell, leaving a background color set.
+        let _ = execute!(
+            io::stdout(),
+            style::ResetColor,
+            cursor::Show,
+            terminal::SetTitle("webcii")
+        );
+        let _ = terminal::disable_raw_mode();
+    }
+}
+
+/// Live status shown in the terminal title bar (OSC 0), e.g.
+/// `webcii — cam0 30fps 41ms`: the frame's source, the render rate and how
+/// long ago the frame on screen was captured.
+pub fn status_title(source: &str, fps: f32, latency: Duration) -> String {
+    format!(
+        "webcii — {} {:.0}fps {}ms",
+        source,
+        fps,
+        latency.as_millis()
+    )
+}
@@ -0,0 +1,163 @@
+//! Asking the terminal what it supports: escape sequence queries written to
+//! the tty and answered on it.
+
+use std::{env, time::Duration};
+
+/// How long to wait for an answer; a terminal that doesn't know a query
+/// never answers it.
+pub const QUERY_TIMEOUT: Duration = Duration::from_millis(200);
+
+/// Pixel size of one cell, assumed when the terminal doesn't report its
+/// size in pixels.
+pub const FALLBACK_CELL_PIXELS: (u32, u32) = (8, 16);
+
+/// Kitty graphics query for a 1x1 image, answered with `OK` by terminals
+/// that speak the protocol and ignored by the rest.
+const KITTY_QUERY: &[u8] = b"\x1b_Gi=31,s=1,v=1,a=q,t=d,f=24;AAAA\x1b\\";
+
+/// Writes `query` to the tty followed by a primary device attributes
+/// request (DA1), which every terminal answers, and returns everything read
+/// up to and including that answer, or `None` if it doesn't come within
+/// `timeout`. Call it in raw mode and before anything else reads keys,
+/// since the answers arrive as input.
+#[cfg(unix)]
+pub fn ask(query: &[u8], timeout: Duration) -> Option<Vec<u8>> {
+    use std::{
+        fs::File,
+        io::{Read, Write},
+        os::fd::AsRawFd,
+        time::Instant,
+    };
+
+    let mut tty = File::options()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .ok()?;
+    tty.write_all(query).ok()?;
+    tty.write_all(b"\x1b[c").ok()?;
+    tty.flush().ok()?;
+
+    let deadline = Instant::now() + timeout;
+    let mut response = Vec::new();
+    loop {
+        let left = deadline.checked_duration_since(Instant::now())?;
+        let mut fd = libc::pollfd {
+            fd: tty.as_raw_fd(),
+            events: libc::POLLIN,
+            revents: 0,
+        };
+        // SAFETY: `fd` is a single valid pollfd for the duration of the call.
+        let ready = unsafe { libc::poll(&mut fd, 1, left.as_millis() as libc::c_int) };
+        if ready <= 0 {
+            return None;
+        }
+        let mut byte = [0];
+        if tty.read(&mut byte).ok()? == 0 {
+            return None;
+        }
+        response.push(byte[0]);
+        if byte[0] == b'c' && parse_device_attributes(&response).is_some() {
+            return Some(response);
+        }
+    }
+}
+
+#[cfg(not(unix))]
+pub fn ask(_query: &[u8], _timeout: Duration) -> Option<Vec<u8>> {
+    None
+}
+
+/// The parameters of the DA1 answer at the end of `response`,
+/// `ESC [ ? Ps ; ... c`, ignoring anything before it.
+pub fn parse_device_attributes(response: &[u8]) -> Option<Vec<u32>> {
+    let start = response.windows(3).rposition(|w| w == b"\x1b[?")? + 3;
+    let body = response[start..].strip_suffix(b"c")?;
+    std::str::from_utf8(body)
+        .ok()?
+        .split(';')
+        .map(|param| param.parse().ok())
+        .collect()
+}
+
+/// The graphics protocols a terminal says it supports.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct GraphicsSupport {
+    /// The kitty graphics protocol.
+    pub kitty: bool,
+    /// iTerm2's inline images, which it announces in the environment
+    /// rather than in an answer.
+    pub iterm2: bool,
+    /// Sixel (DA1 attribute 4).
+    pub sixel: bool,
+}
+
+impl GraphicsSupport {
+    /// Reads an answer to the kitty query followed by DA1.
+    pub fn parse(response: &[u8]) -> Self {
+        let kitty = response.windows(9).any(|w| w == b"_Gi=31;OK");
+        let sixel = parse_device_attributes(response).is_some_and(|attributes| {
+            // The first parameter is the terminal class, not an attribute.
+            attributes.iter().skip(1).any(|&attribute| attribute == 4)
+        });
+        Self {
+            kitty,
+            sixel,
+            ..Self::default()
+        }
+    }
+}
+
+/// Asks the terminal which graphics protocols it supports; none if it
+/// doesn't answer.
+pub fn graphics_support() -> GraphicsSupport {
+    let mut support = ask(KITTY_QUERY, QUERY_TIMEOUT)
+        .map(|response| GraphicsSupport::parse(&response))
+        .unwrap_or_default();
+    // LC_TERMINAL makes it through ssh, TERM_PROGRAM only locally.
+    support.iterm2 = env::var("LC_TERMINAL").is_ok_and(|terminal| terminal == "iTerm2")
+        || env::var("TERM_PROGRAM").is_ok_and(|program| program == "iTerm.app");
+    support
+}
+
+/// Size of one cell in pixels, from the window size the terminal reports.
+pub fn cell_pixels() -> (u32, u32) {
+    match crossterm::terminal::window_size() {
+        Ok(size) if size.width > 0 && size.height > 0 && size.columns > 0 && size.rows > 0 => (
+            (size.width / size.columns) as u32,
+            (size.height / size.rows) as u32,
+        ),
+        _ => FALLBACK_CELL_PIXELS,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn device_attributes_are_parsed_after_stray_input() {
+        assert_eq!(
+            parse_device_attributes(b"q\x1b[?62;4;6;22c"),
+            Some(vec![62, 4, 6, 22])
+        );
+        assert_eq!(parse_device_attributes(b"\x1b[?1;2c"), Some(vec![1, 2]));
+        assert_eq!(parse_device_attributes(b"\x1b[0n"), None);
+    }
+
+    #[test]
+    fn graphics_support_comes_from_both_answers() {
+        assert_eq!(
+            GraphicsSupport::parse(b"\x1b_Gi=31;OK\x1b\\\x1b[?62;22c"),
+            GraphicsSupport {
+                kitty: true,
+                ..GraphicsSupport::default()
+            }
+        );
+        assert_eq!(
+            GraphicsSupport::parse(b"\x1b[?4;6c"),
+            GraphicsSupport::default()
+        );
+        assert!(GraphicsSupport::parse(b"\x1b[?62;4;22c").sixel);
+    }
+}
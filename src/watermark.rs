@@ -0,0 +1,349 @@
+//! Settings watermarks: `--watermark` stamps exported frames with the
+//! settings they were rendered with, and `--apply-settings` reads them back.
+//!
+//! The settings travel as one line of `key=value` pairs, e.g.
+//! `...,colorizer=ansi8,...,mode=braille,...,zebra=true`, short enough to
+//! paste, and a hash of that line tells two looks apart at a glance.
+
+use std::{error::Error, fs, io::BufReader, path::Path};
+
+use serde_json::{Map, Value};
+use webcii::render::settings::RenderSettings;
+
+/// PNG text keywords.
+pub const SETTINGS_KEY: &str = "webcii-settings";
+pub const HASH_KEY: &str = "webcii-settings-hash";
+
+/// Width of a SAUCE comment line.
+const SAUCE_LINE: usize = 64;
+
+/// Every field of `settings` as `key=value` pairs, sorted by key. Text
+/// values are written bare unless they would read back as something else,
+/// e.g. a charset with a comma or a leading space, which is quoted.
+pub fn settings_string(settings: &RenderSettings) -> String {
+    let Ok(Value::Object(fields)) = serde_json::to_value(settings) else {
+        unreachable!("settings serialize to an object");
+    };
+    fields
+        .iter()
+        .map(|(key, value)| match value {
+            Value::String(text) if !needs_quotes(text) => format!("{}={}", key, text),
+            value => format!("{}={}", key, value),
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+fn needs_quotes(text: &str) -> bool {
+    text.is_empty()
+        || text.contains([',', '"'])
+        || text.trim() != text
+        || serde_json::from_str::<Value>(text).is_ok()
+}
+
+/// Splits `line` at the commas outside quoted values.
+fn split_pairs(line: &str) -> Vec<&str> {
+    let mut pairs = Vec::new();
+    let (mut start, mut quoted, mut escaped) = (0, false, false);
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' if quoted => escaped = true,
+            '"' => quoted = !quoted,
+            ',' if !quoted => {
+                pairs.push(&line[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    pairs.push(&line[start..]);
+    pairs
+}
+
+/// Reads a line written by [`settings_string`]. Fields left out keep their
+/// defaults; unknown ones are an error, so typos don't go unnoticed.
+pub fn parse_settings_string(line: &str) -> Result<RenderSettings, String> {
+    let Ok(Value::Object(known)) = serde_json::to_value(RenderSettings::default()) else {
+        unreachable!("settings serialize to an object");
+    };
+    let mut fields = Map::new();
+    for pair in split_pairs(line.trim()) {
+        if pair.is_empty() {
+            continue;
+        }
+        let (key, value) = pair
+            .split_once('=')
+            .ok_or_else(|| format!("expected key=value, got `{}`", pair))?;
+        if !known.contains_key(key) {
+            return Err(format!("unknown setting `{}`", key));
+        }
+        let value =
+            serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()));
+        fields.insert(key.to_string(), value);
+    }
+    let settings: RenderSettings =
+        serde_json::from_value(Value::Object(fields)).map_err(|e| e.to_string())?;
+    settings.validate()?;
+    Ok(settings)
+}
+
+/// Short fingerprint of a settings line: FNV-1a, in hex.
+pub fn hash(line: &str) -> String {
+    let hash = line.bytes().fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| {
+        (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+    });
+    format!("{:016x}", hash)
+}
+
+/// Whether `value` looks like a [`hash`].
+fn is_hash(value: &str) -> bool {
+    value.len() == 16 && value.bytes().all(|b| b.is_ascii_hexdigit())
+}
+
+/// Settings for `--apply-settings`: from the watermark of an exported PNG
+/// or ANSI file, a settings line given directly, or an export's manifest,
+/// see [`from_manifest`].
+pub fn load(value: &str) -> Result<RenderSettings, Box<dyn Error>> {
+    let (target, wanted) = match value.rsplit_once('#') {
+        Some((target, wanted)) if is_hash(wanted) => (target, Some(wanted)),
+        _ if is_hash(value) && !Path::new(value).exists() => (".", Some(value)),
+        _ => (value, None),
+    };
+    let path = Path::new(target);
+    if path.is_dir() {
+        return from_manifest(&path.join("manifest.json"), wanted);
+    }
+    if wanted.is_some() || path.file_name().is_some_and(|name| name == "manifest.json") {
+        return from_manifest(path, wanted);
+    }
+    if !path.is_file() {
+        return Ok(parse_settings_string(value)?);
+    }
+    let line = if path
+        .extension()
+        .is_some_and(|ext| ext.eq_ignore_ascii_case("png"))
+    {
+        png_text(path, SETTINGS_KEY)?
+    } else {
+        sauce_comment(&fs::read(path)?)
+    };
+    let line = line.ok_or_else(|| format!("{}: no webcii settings watermark", path.display()))?;
+    parse_settings_string(&line).map_err(|e| format!("{}: {}", path.display(), e).into())
+}
+
+/// Settings from the export manifest at `path`: those of the frames whose
+/// settings hash to `wanted`, or without one, the settings every frame
+/// shares. A hash can't be turned back into settings, so it has to come
+/// with the export it was made for.
+fn from_manifest(path: &Path, wanted: Option<&str>) -> Result<RenderSettings, Box<dyn Error>> {
+    let text = fs::read_to_string(path).map_err(|e| match wanted {
+        Some(wanted) => format!(
+            "{}: {}; settings hash {} can only be looked up in the manifest.json of \
+             its export, give its directory as DIR#HASH or pass an exported file \
+             or settings line instead",
+            path.display(),
+            e,
+            wanted
+        ),
+        None => format!("{}: {}", path.display(), e),
+    })?;
+    let manifest: Value =
+        serde_json::from_str(&text).map_err(|e| format!("{}: {}", path.display(), e))?;
+    let mut looks = Vec::new();
+    for frame in manifest["frames"].as_array().into_iter().flatten() {
+        let settings: RenderSettings = serde_json::from_value(frame["settings"].clone())
+            .map_err(|e| format!("{}: {}", path.display(), e))?;
+        let frame_hash = frame["settings_hash"]
+            .as_str()
+            .map(String::from)
+            .unwrap_or_else(|| hash(&settings_string(&settings)));
+        if !looks.iter().any(|(known, _)| *known == frame_hash) {
+            looks.push((frame_hash, settings));
+        }
+    }
+    match wanted {
+        Some(wanted) => looks
+            .into_iter()
+            .find(|(known, _)| known.eq_ignore_ascii_case(wanted))
+            .map(|(_, settings)| settings)
+            .ok_or_else(|| {
+                format!("{}: no frame with settings hash {}", path.display(), wanted).into()
+            }),
+        None if looks.len() == 1 => Ok(looks.remove(0).1),
+        None if looks.is_empty() => Err(format!("{}: no frames", path.display()).into()),
+        None => {
+            let hashes: Vec<_> = looks.iter().map(|(known, _)| known.as_str()).collect();
+            Err(format!(
+                "{}: frames have different looks; pick one with #HASH: {}",
+                path.display(),
+                hashes.join(", ")
+            )
+            .into())
+        }
+    }
+}
+
+/// The `keyword` text chunk of the PNG at `path`, if it has one: UTF-8
+/// `iTXt`, or the Latin-1 `tEXt` older exports were written with.
+fn png_text(path: &Path, keyword: &str) -> Result<Option<String>, Box<dyn Error>> {
+    let decoder = png::Decoder::new(BufReader::new(fs::File::open(path)?));
+    let reader = decoder.read_info()?;
+    let info = reader.info();
+    if let Some(chunk) = info.utf8_text.iter().find(|chunk| chunk.keyword == keyword) {
+        return Ok(Some(chunk.get_text()?));
+    }
+    Ok(info
+        .uncompressed_latin1_text
+        .iter()
+        .find(|chunk| chunk.keyword == keyword)
+        .map(|chunk| chunk.text.clone()))
+}
+
+/// A SAUCE record for an ANSI file of `cols` x `rows` cells titled
+/// `title`, with `comment` split over as many comment lines as it takes.
+pub fn sauce(title: &str, comment: &str, cols: usize, rows: usize) -> Vec<u8> {
+    let lines: Vec<&[u8]> = comment.as_bytes().chunks(SAUCE_LINE).take(255).collect();
+    let field = |text: &[u8], width: usize| {
+        let mut field = text[..text.len().min(width)].to_vec();
+        field.resize(width, b' ');
+        field
+    };
+
+    // End of file marker, then the comment block, then the record.
+    let mut out = vec![0x1a];
+    if !lines.is_empty() {
+        out.extend_from_slice(b"COMNT");
+        for line in &lines {
+            out.extend(field(line, SAUCE_LINE));
+        }
+    }
+    out.extend_from_slice(b"SAUCE00");
+    out.extend(field(title.as_bytes(), 35));
+    out.extend(field(b"", 20)); // author
+    out.extend(field(b"", 20)); // group
+    out.extend(field(b"", 8)); // date
+    out.extend_from_slice(&0u32.to_le_bytes()); // file size, unknown
+    out.extend_from_slice(&[1, 1]); // character data, ANSi
+    out.extend_from_slice(&(cols.min(u16::MAX as usize) as u16).to_le_bytes());
+    out.extend_from_slice(&(rows.min(u16::MAX as usize) as u16).to_le_bytes());
+    out.extend_from_slice(&[0; 4]);
+    out.push(lines.len() as u8);
+    out.push(0); // flags
+    out.extend_from_slice(&[0; 22]); // font name
+    out
+}
+
+/// The comment of the SAUCE record at the end of `data`, its lines joined
+/// back together.
+fn sauce_comment(data: &[u8]) -> Option<String> {
+    let record = data.len().checked_sub(128)?;
+    if !data[record..].starts_with(b"SAUCE00") {
+        return None;
+    }
+    let lines = data[record + 104] as usize;
+    let block = record.checked_sub(5 + lines * SAUCE_LINE)?;
+    if lines == 0 || !data[block..].starts_with(b"COMNT") {
+        return None;
+    }
+    let comment = &data[block + 5..record];
+    Some(String::from_utf8_lossy(comment).trim_end().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use webcii::render::{
+        color::Colorizer,
+        settings::{EdgeColor, Mode},
+    };
+
+    use super::*;
+
+    fn settings() -> RenderSettings {
+        RenderSettings {
+            mode: Mode::Braille,
+            colorizer: Colorizer::Ansi8,
+            zebra: true,
+            ..RenderSettings::default()
+        }
+    }
+
+    #[test]
+    fn settings_round_trip_through_the_line() {
+        let line = settings_string(&settings());
+        assert!(line.starts_with("adaptive_charset=false,blobs=false,brightness=0,charset=null,"));
+        assert!(line.contains(",colorizer=ansi8,"));
+        assert!(line.contains(",mode=braille,"));
+        assert!(line.ends_with(",zebra=true"));
+        assert_eq!(
+            settings_string(&parse_settings_string(&line).unwrap()),
+            line
+        );
+        assert_ne!(
+            hash(&line),
+            hash(&settings_string(&RenderSettings::default()))
+        );
+
+        let quoted = RenderSettings {
+            charset: Some(" .,:#".to_string()),
+            ..settings()
+        };
+        let line = settings_string(&quoted);
+        assert!(line.contains(r#",charset=" .,:#","#));
+        let parsed = parse_settings_string(&line).unwrap();
+        assert_eq!(parsed.charset.as_deref(), Some(" .,:#"));
+
+        let edges = RenderSettings {
+            edge_color: EdgeColor::Fixed([255, 136, 0]),
+            ..settings()
+        };
+        let line = settings_string(&edges);
+        assert!(line.contains(",edge_color=#ff8800,"));
+        assert!(
+            parse_settings_string(&line).unwrap().edge_color == EdgeColor::Fixed([255, 136, 0])
+        );
+
+        assert!(parse_settings_string("zebar=true").is_err());
+        assert!(parse_settings_string("mode=sideways").is_err());
+        assert!(parse_settings_string("zebra").is_err());
+        assert!(parse_settings_string("peaking_color=9").is_err());
+        assert!(parse_settings_string("overlay_opacity=1.5").is_err());
+    }
+
+    #[test]
+    fn hashes_are_looked_up_in_the_manifest() {
+        let dir = std::env::temp_dir().join(format!("webcii-manifest-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let frames: Vec<_> = [settings(), RenderSettings::default()]
+            .iter()
+            .map(|settings| {
+                serde_json::json!({
+                    "settings": settings,
+                    "settings_hash": hash(&settings_string(settings)),
+                })
+            })
+            .collect();
+        let manifest = serde_json::json!({ "every": 1, "frames": frames });
+        fs::write(dir.join("manifest.json"), manifest.to_string()).unwrap();
+
+        let wanted = hash(&settings_string(&settings()));
+        let found = load(&format!("{}#{}", dir.display(), wanted));
+        let ambiguous = load(&dir.display().to_string());
+        let missing = load(&format!("{}#{}", dir.display(), "0".repeat(16)));
+        let _ = fs::remove_dir_all(&dir);
+
+        assert!(found.unwrap().mode == Mode::Braille);
+        assert!(ambiguous.is_err_and(|e| e.to_string().contains(&wanted)));
+        assert!(missing.is_err());
+        assert!(is_hash(&wanted) && !is_hash("mode=braille"));
+    }
+
+    #[test]
+    fn sauce_comment_carries_the_line() {
+        let line = settings_string(&settings());
+        let mut file = b"\x1b[0mab\r\n".to_vec();
+        file.extend(sauce("webcii", &line, 2, 1));
+        assert_eq!(sauce_comment(&file), Some(line));
+        assert_eq!(sauce_comment(b"no record here"), None);
+    }
+}